@@ -20,28 +20,198 @@
 #[cfg(test)]
 mod tests;
 
-use support::traits::{Currency, ReservableCurrency};
+use support::traits::{Currency, ReservableCurrency, EnsureOrigin, Imbalance, OnUnbalanced};
 use rstd::prelude::*;
+use rstd::result;
 
 use system::ensure_signed;
+use system::offchain::SubmitSignedTransaction;
 use support::dispatch::Result;
 use codec::{Decode, Encode};
 
-use sr_primitives::RuntimeDebug;
-use sr_primitives::traits::{Hash, Zero};
+use sr_primitives::{KeyTypeId, Perbill, RuntimeDebug};
+use sr_primitives::offchain::http;
+use sr_primitives::traits::{Hash, One, Zero, Verify, IdentifyAccount};
+use substrate_primitives::offchain::{Duration, StorageKind};
 use support::{decl_event, decl_module, decl_storage, ensure, StorageMap};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+
+/// Key type used by verifiers to sign the off-chain worker's transactions.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"iden");
+
+/// Prefix of the signed statement expected in the document at a proof URL.
+pub const STATEMENT_PREFIX: &[u8] = b"edgeware-identity:0x";
+
+/// Identity type of Ethereum addresses, which are linked by signature
+/// instead of being verified by the verifiers.
+pub const ETHEREUM_IDENTITY_TYPE: &[u8] = b"ethereum";
+
+/// Prefix of the message signed by an Ethereum address to link an account.
+pub const ETHEREUM_LINK_PREFIX: &[u8] = b"Link Edgeware account:";
+
+/// Ratio of the registration bond to the per-byte metadata deposit set on
+/// chains upgraded from before metadata deposits were introduced.
+pub const METADATA_BYTE_DEPOSIT_RATIO: u32 = 10_000;
+
+/// Ratio of the registration bond to the verification fee set on chains
+/// upgraded from before verifiers were paid.
+pub const VERIFICATION_FEE_RATIO: u32 = 10;
+
+/// Percentage of a slashed bond paid to the verifiers who denied it on
+/// chains upgraded from before verifiers were paid.
+pub const DEFAULT_VERIFIER_SLASH_PERCENT: u32 = 50;
+
+/// Timeout for fetching a proof URL from the off-chain worker.
+const HTTP_TIMEOUT_MS: u64 = 5_000;
+
+/// Maximum number of attestations checked by the off-chain worker per block.
+const MAX_CHECKS_PER_BLOCK: usize = 8;
+
+pub mod crypto {
+    pub use super::KEY_TYPE;
+    use sr_primitives::app_crypto::{app_crypto, sr25519};
+    app_crypto!(sr25519, KEY_TYPE);
+}
+
+/// Submits the off-chain worker's decisions as transactions signed by a
+/// local verifier key.
+pub trait SubmitVerification<T: Trait> {
+    /// Whether this node holds a key belonging to one of `verifiers`.
+    fn has_verifier_key(verifiers: Vec<T::AccountId>) -> bool;
+    /// Submit `call` from the first local key belonging to one of
+    /// `verifiers`, returning whether the transaction was submitted.
+    fn submit_from_verifier(call: Call<T>, verifiers: Vec<T::AccountId>) -> bool;
+}
+
+/// Submits verifications through a `SubmitSignedTransaction` implementation.
+pub struct SignedSubmitter<S>(rstd::marker::PhantomData<S>);
+
+impl<T: Trait, S: SubmitSignedTransaction<T, <T as Trait>::Call>> SubmitVerification<T> for SignedSubmitter<S> {
+    fn has_verifier_key(verifiers: Vec<T::AccountId>) -> bool {
+        !S::find_local_keys(Some(verifiers)).is_empty()
+    }
+
+    fn submit_from_verifier(call: Call<T>, verifiers: Vec<T::AccountId>) -> bool {
+        match S::find_local_keys(Some(verifiers)).into_iter().next() {
+            Some((account, _)) => S::submit_signed_from(call, vec![account])
+                .into_iter()
+                .any(|(_, res)| res.is_ok()),
+            None => false,
+        }
+    }
+}
+
+/// Lets other modules check the verified identities of an account.
+pub trait IdentityProvider<AccountId> {
+    /// Whether the account has a verified identity of the given type.
+    fn is_verified(who: &AccountId, identity_type: &[u8]) -> bool;
+    /// The types of the verified identities of the account.
+    fn verified_types(who: &AccountId) -> Vec<IdentityType>;
+}
+
+impl<AccountId> IdentityProvider<AccountId> for () {
+    fn is_verified(_who: &AccountId, _identity_type: &[u8]) -> bool {
+        false
+    }
+
+    fn verified_types(_who: &AccountId) -> Vec<IdentityType> {
+        Vec::new()
+    }
+}
 
 pub trait Trait: balances::Trait {
     /// The overarching event type.
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
     /// The account balance.
     type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+    /// Origin from which the verifier set and identity types can be managed,
+    /// and appeals can be decided.
+    type VerifierOrigin: EnsureOrigin<Self::Origin>;
+    /// Handler for the part of slashed registration bonds not paid to verifiers.
+    type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
+    /// A dispatchable call type.
+    type Call: From<Call<Self>>;
+    /// Submits the verifier off-chain worker's transactions.
+    type SubmitTransaction: SubmitVerification<Self>;
+    /// Signature of the statement published at an attestation's proof URL.
+    type StatementSignature: Verify<Signer = Self::StatementSigner> + Decode;
+    /// Signer of an attestation statement, identifying an account.
+    type StatementSigner: IdentifyAccount<AccountId = Self::AccountId>;
 }
 
 pub type Attestation = Vec<u8>;
 pub type IdentityType = Vec<u8>;
 pub type Identity = Vec<u8>;
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::NegativeImbalance;
+
+#[derive(Encode, Decode, Default, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct VerifierRecord {
+    /// Number of identities verified with this verifier's approval
+    pub verified: u32,
+    /// Number of identities denied with this verifier's vote
+    pub denied: u32,
+    /// Number of decisions where this verifier was outvoted by the quorum
+    pub overturned: u32,
+}
+
+/// Characters allowed in the identities of an identity type
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum IdentityCharset {
+    /// ASCII letters, digits, `-`, `_` and `.`, e.g. usernames
+    Handle,
+    /// Lowercase hexadecimal digits, e.g. hashes and addresses
+    Hex,
+    /// Any printable ASCII character except whitespace
+    Printable,
+}
+
+impl IdentityCharset {
+    /// Whether the character is allowed by this charset
+    pub fn allows(&self, c: u8) -> bool {
+        match self {
+            IdentityCharset::Handle => c.is_ascii_alphanumeric() || c == b'-' || c == b'_' || c == b'.',
+            IdentityCharset::Hex => c.is_ascii_digit() || (b'a'..=b'f').contains(&c),
+            IdentityCharset::Printable => c.is_ascii_graphic(),
+        }
+    }
+}
+
+/// Format that identities of an allowed identity type must match
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct IdentityTypeRules {
+    /// Minimum length of an identity in bytes
+    pub min_length: u32,
+    /// Maximum length of an identity in bytes
+    pub max_length: u32,
+    /// Characters allowed in an identity
+    pub charset: IdentityCharset,
+}
+
+impl IdentityTypeRules {
+    /// Whether an identity matches these rules
+    pub fn allows(&self, identity: &[u8]) -> bool {
+        !identity.is_empty()
+            && identity.len() >= self.min_length as usize
+            && identity.len() <= self.max_length as usize
+            && identity.iter().all(|c| self.charset.allows(*c))
+    }
+}
+
+/// The identity types allowed on chains that predate the identity type
+/// registry, along with their rules
+pub fn default_identity_types() -> Vec<(IdentityType, IdentityTypeRules)> {
+    let rules = |min_length, max_length, charset| IdentityTypeRules { min_length, max_length, charset };
+    vec![
+        (b"github".to_vec(), rules(1, 39, IdentityCharset::Handle)),
+        (b"twitter".to_vec(), rules(1, 15, IdentityCharset::Handle)),
+        (b"email-hash".to_vec(), rules(64, 64, IdentityCharset::Hex)),
+        (ETHEREUM_IDENTITY_TYPE.to_vec(), rules(40, 40, IdentityCharset::Hex)),
+    ]
+}
 
 #[derive(Encode, Decode, Clone, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug)]
 pub struct MetadataRecord {
@@ -55,6 +225,7 @@ pub enum IdentityStage {
     Registered,
     Attested,
     Verified,
+    Denied,
 }
 
 #[derive(Encode, Decode, Clone, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug)]
@@ -91,6 +262,10 @@ decl_module! {
         /// Attestation is only valid if the identity is in the attestation phase
         /// and is verified off-chain using an off-chain worker node. Current
         /// implementation overwrites all proofs if safety checks pass.
+        ///
+        /// For Ethereum identities the attestation is an ECDSA signature by the
+        /// address over the sender's account ID, and a valid signature verifies
+        /// the identity immediately.
         pub fn attest(origin, identity_hash: T::Hash, attestation: Attestation) -> Result {
             let _sender = ensure_signed(origin)?;
             return Self::do_attest(_sender, identity_hash, attestation);
@@ -115,10 +290,9 @@ decl_module! {
         ///
         /// The verification is handled by a set of seeded verifiers who run
         /// the off-chain worker node to verify attestations.
-        pub fn verify(origin, identity_hash: T::Hash, verifier_index: u32) -> Result {
+        pub fn verify(origin, identity_hash: T::Hash) -> Result {
             let _sender = ensure_signed(origin)?;
-            ensure!((verifier_index as usize) < Self::verifiers().len(), "Verifier index out of bounds");
-            ensure!(Self::verifiers()[verifier_index as usize] == _sender.clone(), "Sender is not a verifier");
+            ensure!(Self::is_verifier(&_sender), "Sender is not a verifier");
             return Self::verify_or_deny_identity(_sender, &identity_hash, true);
         }
 
@@ -126,18 +300,16 @@ decl_module! {
         ///
         /// The verification is handled by a set of seeded verifiers who run
         /// the off-chain worker node to verify attestations.
-        pub fn deny(origin, identity_hash: T::Hash, verifier_index: u32) -> Result {
+        pub fn deny(origin, identity_hash: T::Hash) -> Result {
             let _sender = ensure_signed(origin)?;
-            ensure!((verifier_index as usize) < Self::verifiers().len(), "Verifier index out of bounds");
-            ensure!(Self::verifiers()[verifier_index as usize] == _sender.clone(), "Sender is not a verifier");
+            ensure!(Self::is_verifier(&_sender), "Sender is not a verifier");
             return Self::verify_or_deny_identity(_sender, &identity_hash, false);
         }
 
         /// Verify many verification requests
-        pub fn verify_many(origin, identity_hashes: Vec<T::Hash>, verifier_index: u32) -> Result {
+        pub fn verify_many(origin, identity_hashes: Vec<T::Hash>) -> Result {
             let _sender = ensure_signed(origin)?;
-            ensure!((verifier_index as usize) < Self::verifiers().len(), "Verifier index out of bounds");
-            ensure!(Self::verifiers()[verifier_index as usize] == _sender.clone(), "Sender is not a verifier");
+            ensure!(Self::is_verifier(&_sender), "Sender is not a verifier");
 
             for i in 0..identity_hashes.len() {
                 Self::verify_or_deny_identity(_sender.clone(), &identity_hashes[i], true)?;
@@ -147,10 +319,9 @@ decl_module! {
         }
 
         /// Deny many verification requests
-        pub fn deny_many(origin, identity_hashes: Vec<T::Hash>, verifier_index: u32) -> Result {
+        pub fn deny_many(origin, identity_hashes: Vec<T::Hash>) -> Result {
             let _sender = ensure_signed(origin)?;
-            ensure!((verifier_index as usize) < Self::verifiers().len(), "Verifier index out of bounds");
-            ensure!(Self::verifiers()[verifier_index as usize] == _sender.clone(), "Sender is not a verifier");
+            ensure!(Self::is_verifier(&_sender), "Sender is not a verifier");
 
             for i in 0..identity_hashes.len() {
                 Self::verify_or_deny_identity(_sender.clone(), &identity_hashes[i], false)?;
@@ -159,13 +330,136 @@ decl_module! {
             Ok(())
         }
 
+        /// Add an account to the verifier set.
+        pub fn add_verifier(origin, verifier: T::AccountId) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            ensure!(!Self::is_verifier(&verifier), "Account is already a verifier");
+            <Verifiers<T>>::mutate(|verifiers| verifiers.push(verifier.clone()));
+            Self::deposit_event(RawEvent::VerifierAdded(verifier));
+            Ok(())
+        }
+
+        /// Remove an account from the verifier set.
+        pub fn remove_verifier(origin, verifier: T::AccountId) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            ensure!(Self::is_verifier(&verifier), "Account is not a verifier");
+            Self::ensure_quorum_reachable(Self::verifiers().len() - 1)?;
+            <Verifiers<T>>::mutate(|verifiers| verifiers.retain(|v| v != &verifier));
+            Self::deposit_event(RawEvent::VerifierRemoved(verifier));
+            Ok(())
+        }
+
+        /// Remove all verifiers whose decisions were overturned more than `max_overturned` times.
+        pub fn remove_overturned_verifiers(origin, max_overturned: u32) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            let (removed, kept): (Vec<_>, Vec<_>) = Self::verifiers().into_iter()
+                .partition(|v| Self::verifier_stats(v).overturned > max_overturned);
+            Self::ensure_quorum_reachable(kept.len())?;
+            removed.into_iter().for_each(|v| Self::deposit_event(RawEvent::VerifierRemoved(v)));
+            <Verifiers<T>>::put(kept);
+            Ok(())
+        }
+
+        /// Allow an identity type, or replace the rules of an allowed identity type.
+        pub fn set_identity_type(origin, identity_type: IdentityType, rules: IdentityTypeRules) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            ensure!(!identity_type.is_empty(), "Identity type must not be empty");
+            ensure!(rules.max_length > 0, "Maximum identity length must be positive");
+            ensure!(rules.min_length <= rules.max_length, "Minimum identity length exceeds maximum");
+            <IdentityTypes>::insert(&identity_type, rules);
+            Self::deposit_event(RawEvent::IdentityTypeSet(identity_type));
+            Ok(())
+        }
+
+        /// Disallow new registrations of an identity type.
+        ///
+        /// Existing identities of the type are kept.
+        pub fn remove_identity_type(origin, identity_type: IdentityType) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            ensure!(<IdentityTypes>::exists(&identity_type), "Identity type not allowed");
+            <IdentityTypes>::remove(&identity_type);
+            Self::deposit_event(RawEvent::IdentityTypeRemoved(identity_type));
+            Ok(())
+        }
+
+        /// Set the number of verifiers that must agree to verify or deny an identity.
+        pub fn set_verification_quorum(origin, quorum: u32) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            ensure!(quorum > 0, "Quorum must be positive");
+            ensure!(quorum as usize <= Self::verifiers().len(), "Quorum exceeds number of verifiers");
+            <VerificationQuorum>::put(quorum);
+            Ok(())
+        }
+
+        /// Replace the verifier set, e.g. to rotate all verifier keys at once.
+        pub fn set_verifiers(origin, verifiers: Vec<T::AccountId>) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            let mut new_verifiers = verifiers;
+            new_verifiers.sort();
+            new_verifiers.dedup();
+            Self::ensure_quorum_reachable(new_verifiers.len())?;
+            let old_verifiers = Self::verifiers();
+            old_verifiers.iter()
+                .filter(|v| !new_verifiers.contains(v))
+                .for_each(|v| Self::deposit_event(RawEvent::VerifierRemoved(v.clone())));
+            new_verifiers.iter()
+                .filter(|v| !old_verifiers.contains(v))
+                .for_each(|v| Self::deposit_event(RawEvent::VerifierAdded(v.clone())));
+            <Verifiers<T>>::put(new_verifiers);
+            Ok(())
+        }
+
+        /// Set the fee paid to the verifiers out of the registration bond upon verification.
+        pub fn set_verification_fee(origin, fee: BalanceOf<T>) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            <VerificationFee<T>>::put(fee);
+            Ok(())
+        }
+
+        /// Set the share of a slashed registration bond paid to the verifiers who denied it.
+        pub fn set_verifier_slash_share(origin, share: Perbill) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            <VerifierSlashShare>::put(share);
+            Ok(())
+        }
+
+        /// Set the deposit reserved per byte of identity metadata.
+        ///
+        /// Deposits already reserved are adjusted when the metadata is next updated.
+        pub fn set_metadata_byte_deposit(origin, deposit: BalanceOf<T>) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            <MetadataByteDeposit<T>>::put(deposit);
+            Ok(())
+        }
+
         /// Add metadata to sender's account.
+        ///
+        /// Reserves a deposit proportional to the length of the metadata, which
+        /// is adjusted when the metadata is replaced.
         pub fn add_metadata(origin, identity_hash: T::Hash, avatar: Vec<u8>, display_name: Vec<u8>, tagline: Vec<u8>) -> Result {
             let _sender = ensure_signed(origin)?;
             let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
             // Check that original sender and current sender match
             ensure!(record.account == _sender, "Stored identity does not match sender");
-            ensure!(<system::Module<T>>::block_number() <= record.expiration_length, "Identity expired");
+            ensure!(
+                record.expiration_length.is_zero() || <system::Module<T>>::block_number() <= record.expiration_length,
+                "Identity expired"
+            );
+            ensure!(avatar.len() <= Self::max_avatar_length() as usize, "Avatar too long");
+            ensure!(display_name.len() <= Self::max_display_name_length() as usize, "Display name too long");
+            ensure!(tagline.len() <= Self::max_tagline_length() as usize, "Tagline too long");
+
+            let bytes = (avatar.len() + display_name.len() + tagline.len()) as u32;
+            let deposit = Self::metadata_byte_deposit() * <BalanceOf<T>>::from(bytes);
+            let old_deposit = Self::metadata_deposit(identity_hash);
+            if deposit > old_deposit {
+                T::Currency::reserve(&_sender, deposit - old_deposit)
+                    .map_err(|_| "Not enough currency for metadata deposit")?;
+            } else {
+                T::Currency::unreserve(&_sender, old_deposit - deposit);
+            }
+            <MetadataDeposit<T>>::insert(identity_hash, deposit);
+
             // Replace all metadata
             let mut new_record = record;
             new_record.metadata = Some(MetadataRecord {
@@ -177,45 +471,497 @@ decl_module! {
             Ok(())
         }
 
+        /// Remove the metadata of sender's identity, refunding the metadata deposit.
+        pub fn clear_metadata(origin, identity_hash: T::Hash) -> Result {
+            let _sender = ensure_signed(origin)?;
+            let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
+            ensure!(record.account == _sender, "Stored identity does not match sender");
+            ensure!(record.metadata.is_some(), "Identity has no metadata");
+            Self::release_metadata_deposit(&identity_hash, &record.account);
+            <IdentityOf<T>>::insert(identity_hash, IdentityRecord { metadata: None, ..record });
+            Self::deposit_event(RawEvent::MetadataCleared(identity_hash));
+            Ok(())
+        }
+
+        /// Remove offensive metadata through governance, slashing the metadata deposit.
+        pub fn force_clear_metadata(origin, identity_hash: T::Hash) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
+            ensure!(record.metadata.is_some(), "Identity has no metadata");
+            let (slashed, _) = T::Currency::slash_reserved(&record.account, <MetadataDeposit<T>>::take(identity_hash));
+            T::Slashed::on_unbalanced(slashed);
+            <IdentityOf<T>>::insert(identity_hash, IdentityRecord { metadata: None, ..record });
+            Self::deposit_event(RawEvent::MetadataCleared(identity_hash));
+            Ok(())
+        }
+
         /// Revoke an identity from the creator/sender of such an identity
+        ///
+        /// Refunds the registration bond of identities that are not verified
+        /// yet, and frees the identity type to be registered again.
         pub fn revoke(origin, identity_hash: T::Hash) -> Result {
             let _sender = ensure_signed(origin)?;
             let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
             // Check that original sender and current sender match
             ensure!(record.account == _sender, "Stored identity does not match sender");
+            ensure!(record.stage != IdentityStage::Denied, "Identity denied");
+            // The bond of verified identities was already returned on verification
+            T::Currency::unreserve(&_sender, <BondOf<T>>::take(identity_hash));
+            Self::release_identity_type(&_sender, &record.identity_type);
             Self::remove_pending_identity(&identity_hash);
+            Self::deposit_event(RawEvent::Revoked(identity_hash, _sender));
+            Ok(())
+        }
+
+        /// Start transferring an identity to another account, e.g. to rotate keys.
+        ///
+        /// The transfer completes once the new account accepts it. Identities
+        /// awaiting verification cannot be transferred, since their proof is
+        /// bound to the current account.
+        pub fn transfer_identity(origin, identity_hash: T::Hash, new_account: T::AccountId) -> Result {
+            let _sender = ensure_signed(origin)?;
+            let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
+            ensure!(record.account == _sender, "Stored identity does not match sender");
+            ensure!(new_account != _sender, "Identity already belongs to account");
+            match record.stage {
+                IdentityStage::Attested => return Err("Identity awaiting verification"),
+                IdentityStage::Denied => return Err("Identity denied"),
+                IdentityStage::Registered | IdentityStage::Verified => (),
+            }
+            <PendingTransfers<T>>::insert(identity_hash, new_account.clone());
+            Self::deposit_event(RawEvent::TransferInitiated(identity_hash, _sender, new_account));
+            Ok(())
+        }
+
+        /// Cancel a pending transfer of sender's identity.
+        pub fn cancel_identity_transfer(origin, identity_hash: T::Hash) -> Result {
+            let _sender = ensure_signed(origin)?;
+            let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
+            ensure!(record.account == _sender, "Stored identity does not match sender");
+            ensure!(<PendingTransfers<T>>::exists(identity_hash), "No pending transfer");
+            <PendingTransfers<T>>::remove(identity_hash);
+            Ok(())
+        }
+
+        /// Accept a pending transfer of an identity to the sender.
+        ///
+        /// The sender takes over the reserved registration bond and metadata
+        /// deposit, which are refunded to the previous account. The identity
+        /// keeps its stage and metadata, except that verified Ethereum
+        /// identities return to the registration phase until the address
+        /// signs for the new account.
+        pub fn accept_identity(origin, identity_hash: T::Hash) -> Result {
+            let _sender = ensure_signed(origin)?;
+            ensure!(Self::pending_transfers(identity_hash) == Some(_sender.clone()), "No pending transfer to sender");
+            let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
+            ensure!(record.stage != IdentityStage::Attested, "Identity awaiting verification");
+            ensure!(record.stage != IdentityStage::Denied, "Identity denied");
+            ensure!(!<UsedTypes<T>>::get(&_sender).contains(&record.identity_type), "Identity type already used");
+
+            let deposit = Self::metadata_deposit(identity_hash) + Self::bond_of(identity_hash);
+            T::Currency::reserve(&_sender, deposit).map_err(|_| "Not enough currency to take over identity deposits")?;
+            T::Currency::unreserve(&record.account, deposit);
+
+            <PendingTransfers<T>>::remove(identity_hash);
+            Self::release_identity_type(&record.account, &record.identity_type);
+            <UsedTypes<T>>::mutate(&_sender, |types| types.push(record.identity_type.clone()));
+            <IdentityHashOf<T>>::insert((_sender.clone(), record.identity_type.clone()), identity_hash);
+            Self::deposit_event(RawEvent::IdentityTransferred(identity_hash, record.account.clone(), _sender.clone()));
+
+            // An Ethereum signature only links the address to the account that signed it
+            let record = if record.identity_type == ETHEREUM_IDENTITY_TYPE && record.stage == IdentityStage::Verified {
+                let expiration = <system::Module<T>>::block_number() + Self::expiration_length();
+                Self::cancel_expiration(&identity_hash, record.expiration_length);
+                Self::schedule_expiration(&identity_hash, expiration);
+                IdentityRecord {
+                    stage: IdentityStage::Registered,
+                    expiration_length: expiration,
+                    proof: None,
+                    ..record
+                }
+            } else {
+                record
+            };
+            <IdentityOf<T>>::insert(identity_hash, IdentityRecord {
+                account: _sender,
+                ..record
+            });
+            Ok(())
+        }
+
+        /// Renew a verified identity with a new attestation.
+        ///
+        /// The registration bond is reserved again and the identity re-enters
+        /// the attestation phase to be checked by the verifiers. Ethereum
+        /// identities are renewed immediately by a new signature.
+        pub fn renew(origin, identity_hash: T::Hash, attestation: Attestation) -> Result {
+            let _sender = ensure_signed(origin)?;
+            let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
+            ensure!(record.account == _sender, "Stored identity does not match sender");
+            ensure!(record.stage == IdentityStage::Verified, "Identity not verified");
+
+            if record.identity_type == ETHEREUM_IDENTITY_TYPE {
+                return Self::link_ethereum_address(_sender, identity_hash, record, attestation);
+            }
+
+            Self::reserve_bond(&_sender, &identity_hash)?;
+            Self::record_attestation(_sender, identity_hash, record, attestation);
             Ok(())
         }
 
-        /// Check all pending identities for expiration when each block is
-        /// finalised. Once an identity expires, it is deleted from storage.
+        /// Appeal a denied identity within its dispute window.
+        ///
+        /// Reserves the appeal deposit and keeps the registration bond reserved
+        /// until a verifier who did not deny the identity, or governance,
+        /// reviews the appeal. If nobody reviews it within the appeal review
+        /// period, the denial stands and only the appeal deposit is returned.
+        pub fn appeal(origin, identity_hash: T::Hash) -> Result {
+            let _sender = ensure_signed(origin)?;
+            let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
+            ensure!(record.account == _sender, "Stored identity does not match sender");
+            ensure!(record.stage == IdentityStage::Denied, "Identity not denied");
+            ensure!(!<Appeals<T>>::exists(identity_hash), "Identity already appealed");
+            ensure!(<system::Module<T>>::block_number() <= record.expiration_length, "Dispute window expired");
+            let deposit = Self::appeal_deposit();
+            T::Currency::reserve(&_sender, deposit).map_err(|_| "Not enough currency for appeal deposit")?;
+            <Appeals<T>>::insert(identity_hash, deposit);
+            // An appealed identity expires with its review deadline instead of its dispute window
+            let deadline = <system::Module<T>>::block_number() + Self::appeal_review_period();
+            Self::cancel_expiration(&identity_hash, record.expiration_length);
+            Self::schedule_expiration(&identity_hash, deadline);
+            <IdentityOf<T>>::insert(identity_hash, IdentityRecord {
+                expiration_length: deadline,
+                ..record
+            });
+            Self::deposit_event(RawEvent::Appealed(identity_hash, _sender));
+            Ok(())
+        }
+
+        /// Review an appealed identity as a verifier who did not deny it.
+        ///
+        /// Approving the appeal restores the bond and verifies the identity,
+        /// rejecting it slashes both the bond and the appeal deposit.
+        pub fn review_appeal(origin, identity_hash: T::Hash, approve: bool) -> Result {
+            let _sender = ensure_signed(origin)?;
+            ensure!(Self::is_verifier(&_sender), "Sender is not a verifier");
+            ensure!(!Self::denied_by(identity_hash).contains(&_sender), "Verifier denied this identity");
+            return Self::do_resolve_appeal(identity_hash, approve);
+        }
+
+        /// Review an appealed identity through governance.
+        pub fn force_review_appeal(origin, identity_hash: T::Hash, approve: bool) -> Result {
+            T::VerifierOrigin::ensure_origin(origin)?;
+            return Self::do_resolve_appeal(identity_hash, approve);
+        }
+
+        /// Move identities from the legacy identity lists into the indexed
+        /// storage on the first block after an upgrade.
+        fn on_initialize(_n: T::BlockNumber) {
+            Self::migrate_identities();
+        }
+
+        /// Check the identities expiring at the previous block when each block
+        /// is finalised. Once an identity expires, it is deleted from storage.
+        ///
+        /// Verified identities that were not renewed in time are downgraded to
+        /// the registration phase. The registration bond is reserved again
+        /// once the identity is attested anew.
         fn on_finalize(_n: T::BlockNumber) {
-            let (expired, valid): (Vec<_>, _) = <IdentitiesPending<T>>::get()
-                .into_iter()
-                .partition(|(_, exp)| (_n > *exp) && (*exp > T::BlockNumber::zero()));
+            if _n.is_zero() {
+                return;
+            }
+            let expired_at = _n - One::one();
+
+            <Expirations<T>>::take(expired_at).into_iter().for_each(|exp_hash| {
+                let id_record = match <IdentityOf<T>>::get(exp_hash) {
+                    // Skip identities whose expiration was moved
+                    Some(record) if record.expiration_length == expired_at => record,
+                    _ => return,
+                };
 
-            expired.into_iter().for_each(move |(exp_hash, _)| {
-                if let Some(id_record) = <IdentityOf<T>>::get(exp_hash) {
-                    let mut types = <UsedTypes<T>>::get(id_record.account.clone());
-                    types.retain(|t| *t != id_record.identity_type.clone());
-                    <UsedTypes<T>>::insert(id_record.account, types);
+                if id_record.stage == IdentityStage::Verified {
+                    let expiration = _n + Self::expiration_length();
+                    <IdentityOf<T>>::insert(exp_hash, IdentityRecord {
+                        stage: IdentityStage::Registered,
+                        expiration_length: expiration,
+                        proof: None,
+                        ..id_record
+                    });
+                    Self::schedule_expiration(&exp_hash, expiration);
+                    Self::deposit_event(RawEvent::VerificationExpired(exp_hash));
+                    return;
                 }
 
-                <Identities<T>>::mutate(|idents| idents.retain(|hash| hash != &exp_hash));
+                // Denials that were not appealed, or whose appeal was not reviewed, in time are final
+                if id_record.stage == IdentityStage::Denied {
+                    if let Some(deposit) = <Appeals<T>>::take(exp_hash) {
+                        T::Currency::unreserve(&id_record.account, deposit);
+                    }
+                    Self::slash_denied_identity(&exp_hash, &id_record.account);
+                } else {
+                    T::Currency::unreserve(&id_record.account, <BondOf<T>>::take(exp_hash));
+                }
+                Self::release_identity_type(&id_record.account, &id_record.identity_type);
+                Self::release_metadata_deposit(&exp_hash, &id_record.account);
+
+                <IdentitySet<T>>::remove(exp_hash);
                 <IdentityOf<T>>::remove(exp_hash);
+                <Approvals<T>>::remove(exp_hash);
+                <PendingTransfers<T>>::remove(exp_hash);
                 Self::deposit_event(RawEvent::Expired(exp_hash))
             });
-            <IdentitiesPending<T>>::put(valid);
+        }
+
+        /// Check attested identities against their proof URLs and submit the
+        /// outcome from a local verifier key, if this node holds one.
+        fn offchain_worker(_now: T::BlockNumber) {
+            Self::verify_pending_attestations();
         }
     }
 }
 
 impl<T: Trait> Module<T> {
+    /// Returns whether an account is in the verifier set
+    pub fn is_verifier(who: &T::AccountId) -> bool {
+        Self::verifiers().contains(who)
+    }
+
+    /// The message an account signs to link itself to an identity
+    pub fn attestation_message(account: &T::AccountId, identity_type: &[u8], identity: &[u8]) -> Vec<u8> {
+        (b"edgeware-identity", account, identity_type, identity).encode()
+    }
+
+    /// Checks attested identities that have not been checked yet and submits
+    /// a `verify` or `deny` transaction for each decided attestation.
+    pub fn verify_pending_attestations() {
+        let verifiers = Self::verifiers();
+        if !T::SubmitTransaction::has_verifier_key(verifiers.clone()) {
+            return;
+        }
+
+        <IdentitySet<T>>::enumerate()
+            .filter_map(|(hash, _)| Self::identity_of(hash).map(|record| (hash, record)))
+            .filter(|(_, record)| record.stage == IdentityStage::Attested)
+            .filter(|(hash, record)| !Self::is_checked(hash, record))
+            .take(MAX_CHECKS_PER_BLOCK)
+            .for_each(|(hash, record)| {
+                let call = match Self::check_attestation(&record) {
+                    Some(true) => Call::verify(hash),
+                    Some(false) => Call::deny(hash),
+                    None => return,
+                };
+                if T::SubmitTransaction::submit_from_verifier(call, verifiers.clone()) {
+                    Self::mark_checked(&hash, &record);
+                }
+            });
+    }
+
+    /// Checks an attestation by fetching its proof URL.
+    ///
+    /// Returns `None` when no decision can be made, e.g. for unsupported
+    /// identity types or when the proof could not be fetched.
+    pub fn check_attestation(record: &IdentityRecord<T::AccountId, T::BlockNumber>) -> Option<bool> {
+        let proof = record.proof.as_ref()?;
+        if !Self::is_supported_type(&record.identity_type) {
+            return None;
+        }
+        if !Self::proof_url_matches(&record.identity_type, &record.identity, proof) {
+            return Some(false);
+        }
+
+        let document = Self::fetch_proof(proof).ok()?;
+        let message = Self::attestation_message(&record.account, &record.identity_type, &record.identity);
+        let verified = Self::find_statement(&document)
+            .and_then(|bytes| T::StatementSignature::decode(&mut &bytes[..]).ok())
+            .map(|signature| signature.verify(&message[..], &record.account))
+            .unwrap_or(false);
+        Some(verified)
+    }
+
+    /// Identity types whose proofs can be checked by the off-chain worker
+    fn is_supported_type(identity_type: &[u8]) -> bool {
+        identity_type == b"github" || identity_type == b"twitter"
+    }
+
+    /// Checks that a proof URL is hosted under the account of the identity
+    fn proof_url_matches(identity_type: &[u8], identity: &[u8], url: &[u8]) -> bool {
+        let prefixes: Vec<Vec<u8>> = match identity_type {
+            b"github" => vec![
+                [&b"https://gist.github.com/"[..], identity, b"/"].concat(),
+                [&b"https://gist.githubusercontent.com/"[..], identity, b"/"].concat(),
+            ],
+            b"twitter" => vec![
+                [&b"https://twitter.com/"[..], identity, b"/status/"].concat(),
+            ],
+            _ => vec![],
+        };
+        prefixes.iter().any(|prefix| url.starts_with(prefix))
+    }
+
+    /// Fetches the document at a proof URL
+    fn fetch_proof(url: &[u8]) -> result::Result<Vec<u8>, &'static str> {
+        let url = rstd::str::from_utf8(url).map_err(|_| "Invalid proof URL")?;
+        let deadline = sr_io::offchain::timestamp().add(Duration::from_millis(HTTP_TIMEOUT_MS));
+        let pending = http::Request::get(url)
+            .deadline(deadline)
+            .send()
+            .map_err(|_| "Proof request failed")?;
+        let response = pending.try_wait(deadline)
+            .map_err(|_| "Proof request timed out")?
+            .map_err(|_| "Proof request failed")?;
+        ensure!(response.code == 200, "Unexpected proof response");
+        Ok(response.body().collect::<Vec<u8>>())
+    }
+
+    /// Extracts the hex-encoded statement signature from a proof document
+    fn find_statement(document: &[u8]) -> Option<Vec<u8>> {
+        let start = document.windows(STATEMENT_PREFIX.len())
+            .position(|window| window == STATEMENT_PREFIX)? + STATEMENT_PREFIX.len();
+        let hex: Vec<u8> = document[start..].iter()
+            .take_while(|c| c.is_ascii_hexdigit())
+            .cloned()
+            .collect();
+        if hex.is_empty() || hex.len() % 2 != 0 {
+            return None;
+        }
+        hex.chunks(2)
+            .map(|pair| {
+                let hi = (pair[0] as char).to_digit(16)?;
+                let lo = (pair[1] as char).to_digit(16)?;
+                Some((hi * 16 + lo) as u8)
+            })
+            .collect()
+    }
+
+    /// Key under which the off-chain worker remembers a checked attestation
+    fn checked_key(identity_hash: &T::Hash) -> Vec<u8> {
+        (b"edge-identity::checked", identity_hash).encode()
+    }
+
+    /// Whether this attestation was already submitted by the off-chain worker.
+    /// A new attestation resets the expiration, and so is checked again.
+    fn is_checked(identity_hash: &T::Hash, record: &IdentityRecord<T::AccountId, T::BlockNumber>) -> bool {
+        sr_io::offchain::local_storage_get(StorageKind::PERSISTENT, &Self::checked_key(identity_hash))
+            .map(|value| value == record.expiration_length.encode())
+            .unwrap_or(false)
+    }
+
+    fn mark_checked(identity_hash: &T::Hash, record: &IdentityRecord<T::AccountId, T::BlockNumber>) {
+        sr_io::offchain::local_storage_set(
+            StorageKind::PERSISTENT,
+            &Self::checked_key(identity_hash),
+            &record.expiration_length.encode(),
+        );
+    }
+
+    /// Hashes of all identities
+    pub fn identities() -> Vec<T::Hash> {
+        <IdentitySet<T>>::enumerate().map(|(hash, _)| hash).collect()
+    }
+
+    /// Schedules an identity to expire after the given block, unless it is zero
+    fn schedule_expiration(identity_hash: &T::Hash, expiration: T::BlockNumber) {
+        if !expiration.is_zero() {
+            <Expirations<T>>::mutate(expiration, |hashes| hashes.push(*identity_hash));
+        }
+    }
+
+    /// Cancels the expiration of an identity scheduled after the given block
+    fn cancel_expiration(identity_hash: &T::Hash, expiration: T::BlockNumber) {
+        if expiration.is_zero() {
+            return;
+        }
+        let mut hashes = <Expirations<T>>::get(expiration);
+        hashes.retain(|hash| hash != identity_hash);
+        if hashes.is_empty() {
+            <Expirations<T>>::remove(expiration);
+        } else {
+            <Expirations<T>>::insert(expiration, hashes);
+        }
+    }
+
+    /// Moves identities from the legacy `Identities` and `IdentitiesPending`
+    /// lists into `IdentitySet`, `IdentityHashOf` and `Expirations`. Does
+    /// nothing once migrated.
+    fn migrate_identities() {
+        if Self::identities_migrated() {
+            return;
+        }
+        for hash in <Identities<T>>::take() {
+            <IdentitySet<T>>::insert(hash, ());
+            if let Some(record) = Self::identity_of(hash) {
+                // Legacy identities hold their bond until they are verified
+                if record.stage != IdentityStage::Verified {
+                    <BondOf<T>>::insert(hash, Self::registration_bond());
+                }
+                <IdentityHashOf<T>>::insert((record.account, record.identity_type), hash);
+            }
+        }
+        for (hash, expiration) in <IdentitiesPending<T>>::take() {
+            Self::schedule_expiration(&hash, expiration);
+        }
+        // Seed the configuration added since the legacy identity lists
+        for (identity_type, rules) in default_identity_types() {
+            if !<IdentityTypes>::exists(&identity_type) {
+                <IdentityTypes>::insert(&identity_type, rules);
+            }
+        }
+        if Self::dispute_window().is_zero() {
+            <DisputeWindow<T>>::put(Self::expiration_length());
+        }
+        if Self::appeal_deposit().is_zero() {
+            <AppealDeposit<T>>::put(Self::registration_bond());
+        }
+        if Self::appeal_review_period().is_zero() {
+            <AppealReviewPeriod<T>>::put(Self::dispute_window());
+        }
+        if Self::verification_quorum() == 0 {
+            <VerificationQuorum>::put(1);
+        }
+        if Self::metadata_byte_deposit().is_zero() {
+            let ratio = <BalanceOf<T>>::from(METADATA_BYTE_DEPOSIT_RATIO);
+            <MetadataByteDeposit<T>>::put(Self::registration_bond() / ratio);
+        }
+        if Self::verification_fee().is_zero() {
+            let ratio = <BalanceOf<T>>::from(VERIFICATION_FEE_RATIO);
+            <VerificationFee<T>>::put(Self::registration_bond() / ratio);
+        }
+        if Self::verifier_slash_share() == Perbill::zero() {
+            <VerifierSlashShare>::put(Perbill::from_percent(DEFAULT_VERIFIER_SLASH_PERCENT));
+        }
+        <IdentitiesMigrated>::put(true);
+    }
+
     /// Removes all data about a pending identity given the hash of the record
     pub fn remove_pending_identity(identity_hash: &T::Hash) {
-        <Identities<T>>::mutate(|idents| idents.retain(|hash| hash != identity_hash));
-        <IdentityOf<T>>::remove(identity_hash);
-        <IdentitiesPending<T>>::mutate(|idents| idents.retain(|(hash, _)| hash != identity_hash));
+        if let Some(record) = <IdentityOf<T>>::take(identity_hash) {
+            Self::cancel_expiration(identity_hash, record.expiration_length);
+            Self::release_metadata_deposit(identity_hash, &record.account);
+        }
+        <IdentitySet<T>>::remove(identity_hash);
+        <Approvals<T>>::remove(identity_hash);
+        <PendingTransfers<T>>::remove(identity_hash);
+        <DeniedBy<T>>::remove(identity_hash);
+        <BondOf<T>>::remove(identity_hash);
+    }
+
+    /// Reserves the registration bond for an identity that holds none
+    fn reserve_bond(account: &T::AccountId, identity_hash: &T::Hash) -> Result {
+        if Self::bond_of(identity_hash).is_zero() {
+            let bond = Self::registration_bond();
+            T::Currency::reserve(account, bond).map_err(|_| "Not enough currency for reserve bond")?;
+            <BondOf<T>>::insert(identity_hash, bond);
+        }
+        Ok(())
+    }
+
+    /// Ensures that `verifier_count` verifiers can still reach the verification quorum
+    fn ensure_quorum_reachable(verifier_count: usize) -> Result {
+        ensure!(
+            Self::verification_quorum() as usize <= verifier_count,
+            "Too few verifiers for the verification quorum"
+        );
+        Ok(())
     }
 
     /// Helper function for executing the verification of identities
@@ -225,47 +971,136 @@ impl<T: Trait> Module<T> {
         match record.stage {
             IdentityStage::Registered => return Err("No attestation to verify"),
             IdentityStage::Verified => return Err("Already verified"),
+            IdentityStage::Denied => return Err("Identity denied"),
             IdentityStage::Attested => ()
         }
 
+        // Record the verifier's decision and wait until a quorum agrees,
+        // ignoring votes of accounts that were removed from the verifier set
+        let verifiers = Self::verifiers();
+        let mut approvals = <Approvals<T>>::get(identity_hash);
+        approvals.retain(|(v, _)| verifiers.contains(v));
+        ensure!(!approvals.iter().any(|(v, _)| v == &sender), "Verifier already voted");
+        approvals.push((sender.clone(), approve));
+        Self::deposit_event(RawEvent::VerifierVoted(*identity_hash, sender.clone(), approve));
+        let agreeing = approvals.iter().filter(|(_, a)| *a == approve).count() as u32;
+        if agreeing < Self::verification_quorum() {
+            <Approvals<T>>::insert(identity_hash, approvals);
+            return Ok(());
+        }
+        <Approvals<T>>::remove(identity_hash);
+
+        // Verifiers on the losing side of the quorum are recorded as overturned
+        let (agreed, overturned): (Vec<_>, Vec<_>) = approvals.into_iter()
+            .partition(|(_, a)| *a == approve);
+        let agreed: Vec<T::AccountId> = agreed.into_iter().map(|(v, _)| v).collect();
+        overturned.iter().for_each(|(v, _)| <VerifierStats<T>>::mutate(v, |stats| stats.overturned += 1));
+        agreed.iter().for_each(|v| <VerifierStats<T>>::mutate(v, |stats| {
+            if approve { stats.verified += 1 } else { stats.denied += 1 }
+        }));
+
         let id_type = record.identity_type.encode().clone();
         let id = record.identity.encode().clone();
         if approve {
-            // Add the registration bond amount on behalf of a successful verification
-            T::Currency::unreserve(&record.account, Self::registration_bond());
-            // Remove identity from list of pending identities
-            <IdentitiesPending<T>>::mutate(|idents| idents.retain(|(hash, _)| hash != identity_hash));
+            // Pay the verification fee out of the bond and return the rest
+            let bond = <BondOf<T>>::take(identity_hash);
+            let verifier_count = <BalanceOf<T>>::from(agreed.len() as u32);
+            let fee_share = Self::verification_fee().min(bond) / verifier_count;
+            let mut paid: BalanceOf<T> = Zero::zero();
+            for verifier in agreed.iter() {
+                if T::Currency::repatriate_reserved(&record.account, verifier, fee_share).is_ok() {
+                    paid += fee_share;
+                }
+            }
+            T::Currency::unreserve(&record.account, bond - paid);
             Self::deposit_event(RawEvent::Verify(*identity_hash, sender, id_type, id));
+            Self::mark_verified(identity_hash, record);
+        } else {
+            // Hold the bond until the dispute window passes without an appeal
+            let dispute_end = <system::Module<T>>::block_number() + Self::dispute_window();
+            <DeniedBy<T>>::insert(identity_hash, agreed);
+            Self::cancel_expiration(identity_hash, record.expiration_length);
+            Self::schedule_expiration(identity_hash, dispute_end);
+            Self::deposit_event(RawEvent::Denied(*identity_hash, sender, id_type, id));
             <IdentityOf<T>>::insert(identity_hash, IdentityRecord {
-                stage: IdentityStage::Verified,
-                expiration_length: T::BlockNumber::zero(),
+                stage: IdentityStage::Denied,
+                expiration_length: dispute_end,
                 ..record
             });
+        }
+
+        Ok(())
+    }
+
+    /// Slashes the bond of a denied identity, rewarding the verifiers who denied it
+    fn slash_denied_identity(identity_hash: &T::Hash, account: &T::AccountId) {
+        let verifiers = <DeniedBy<T>>::take(identity_hash);
+        let bond = <BondOf<T>>::take(identity_hash);
+        let (mut slashed, _) = T::Currency::slash_reserved(account, bond);
+        if !verifiers.is_empty() {
+            let verifier_count = <BalanceOf<T>>::from(verifiers.len() as u32);
+            let reward_share = (Self::verifier_slash_share() * bond) / verifier_count;
+            for verifier in verifiers.iter() {
+                let (reward, rest) = slashed.split(reward_share);
+                T::Currency::resolve_creating(verifier, reward);
+                slashed = rest;
+            }
+        }
+        T::Slashed::on_unbalanced(slashed);
+    }
+
+    /// Refunds the metadata deposit of an identity
+    fn release_metadata_deposit(identity_hash: &T::Hash, account: &T::AccountId) {
+        T::Currency::unreserve(account, <MetadataDeposit<T>>::take(identity_hash));
+    }
+
+    /// Frees an identity type of an account to be registered again
+    fn release_identity_type(account: &T::AccountId, identity_type: &IdentityType) {
+        <UsedTypes<T>>::mutate(account, |types| types.retain(|t| t != identity_type));
+        <IdentityHashOf<T>>::remove((account.clone(), identity_type.clone()));
+    }
+
+    /// Helper function for deciding an appeal against a denied identity
+    fn do_resolve_appeal(identity_hash: T::Hash, approve: bool) -> Result {
+        let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
+        let deposit = <Appeals<T>>::take(identity_hash).ok_or("Identity not appealed")?;
+        if approve {
+            // Restore the bond and deposit, and count the denial as overturned
+            T::Currency::unreserve(&record.account, <BondOf<T>>::take(identity_hash) + deposit);
+            <DeniedBy<T>>::take(identity_hash).iter()
+                .for_each(|v| <VerifierStats<T>>::mutate(v, |stats| stats.overturned += 1));
+            Self::mark_verified(&identity_hash, record);
         } else {
-            T::Currency::slash_reserved(&record.account, Self::registration_bond());
+            Self::slash_denied_identity(&identity_hash, &record.account);
+            let (slashed, _) = T::Currency::slash_reserved(&record.account, deposit);
+            T::Slashed::on_unbalanced(slashed);
+            Self::release_identity_type(&record.account, &record.identity_type);
             Self::remove_pending_identity(&identity_hash);
-            Self::deposit_event(RawEvent::Denied(*identity_hash, sender, id_type, id));
         }
 
+        Self::deposit_event(RawEvent::AppealResolved(identity_hash, approve));
         Ok(())
     }
 
     /// Helper function for executing the registration of identities
     fn do_register_identity(sender: T::AccountId, identity_type: IdentityType, identity: Identity, identity_hash: T::Hash) -> Result {
+        let rules = Self::identity_types(&identity_type).ok_or("Identity type not allowed")?;
+        ensure!(rules.allows(&identity), "Identity does not match identity type");
         ensure!(!<UsedTypes<T>>::get(sender.clone()).iter().any(|i| i == &identity_type), "Identity type already used");
         ensure!(!<IdentityOf<T>>::exists(identity_hash), "Identity already exists");
         // Reserve the registration bond amount
-        T::Currency::reserve(&sender, Self::registration_bond()).map_err(|_| "Not enough currency for reserve bond")?;
+        Self::reserve_bond(&sender, &identity_hash)?;
         // reserve identity type
         let mut types = <UsedTypes<T>>::get(sender.clone());
         types.push(identity_type.clone());
         <UsedTypes<T>>::insert(sender.clone(), types);
+        <IdentityHashOf<T>>::insert((sender.clone(), identity_type.clone()), identity_hash);
 
         // Set expiration time of identity
         let now = <system::Module<T>>::block_number();
         let expiration = now + Self::expiration_length();
         // Add identity record
-        <Identities<T>>::mutate(|idents| idents.push(identity_hash.clone()));
+        <IdentitySet<T>>::insert(identity_hash, ());
         <IdentityOf<T>>::insert(identity_hash, IdentityRecord {
             account: sender.clone(),
             identity_type: identity_type,
@@ -275,27 +1110,124 @@ impl<T: Trait> Module<T> {
             proof: None,
             metadata: None,
         });
-        <IdentitiesPending<T>>::mutate(|idents| idents.push((identity_hash, expiration.clone())));
+        Self::schedule_expiration(&identity_hash, expiration);
         // Fire register event
         Self::deposit_event(RawEvent::Register(identity_hash, sender.into(), expiration));
         Ok(())
     }
 
+    /// Verifies an Ethereum identity whose address signed the account ID
+    fn link_ethereum_address(
+        sender: T::AccountId,
+        identity_hash: T::Hash,
+        record: IdentityRecord<T::AccountId, T::BlockNumber>,
+        signature: Attestation,
+    ) -> Result {
+        let signer = Self::ethereum_signer(&sender, &signature).ok_or("Invalid Ethereum signature")?;
+        ensure!(Self::to_ascii_hex(&signer) == record.identity, "Ethereum signature does not match identity");
+
+        <Approvals<T>>::remove(identity_hash);
+        Self::deposit_event(RawEvent::EthereumLinked(identity_hash, sender, record.identity.clone()));
+        Self::mark_verified(&identity_hash, IdentityRecord {
+            proof: Some(signature),
+            ..record
+        });
+        Ok(())
+    }
+
+    /// Marks an identity as verified until its verification validity period ends
+    fn mark_verified(identity_hash: &T::Hash, record: IdentityRecord<T::AccountId, T::BlockNumber>) {
+        let validity = Self::verification_validity();
+        let expiration = if validity.is_zero() {
+            T::BlockNumber::zero()
+        } else {
+            <system::Module<T>>::block_number() + validity
+        };
+        // Verified identities only expire when they must be renewed
+        Self::cancel_expiration(identity_hash, record.expiration_length);
+        Self::schedule_expiration(identity_hash, expiration);
+        <IdentityOf<T>>::insert(identity_hash, IdentityRecord {
+            stage: IdentityStage::Verified,
+            expiration_length: expiration,
+            ..record
+        });
+    }
+
+    /// The message an Ethereum address signs to link itself to an account,
+    /// following the `personal_sign` format.
+    pub fn ethereum_signable_message(account: &T::AccountId) -> Vec<u8> {
+        let what = account.using_encoded(Self::to_ascii_hex);
+        let mut l = ETHEREUM_LINK_PREFIX.len() + what.len();
+        let mut rev = Vec::new();
+        while l > 0 {
+            rev.push(b'0' + (l % 10) as u8);
+            l /= 10;
+        }
+        let mut v = b"\x19Ethereum Signed Message:\n".to_vec();
+        v.extend(rev.into_iter().rev());
+        v.extend_from_slice(ETHEREUM_LINK_PREFIX);
+        v.extend_from_slice(&what[..]);
+        v
+    }
+
+    /// Recovers the Ethereum address that signed the link message of an account
+    pub fn ethereum_signer(account: &T::AccountId, signature: &[u8]) -> Option<[u8; 20]> {
+        if signature.len() != 65 {
+            return None;
+        }
+        let mut sig = [0u8; 65];
+        sig.copy_from_slice(signature);
+        let msg = sr_io::hashing::keccak_256(&Self::ethereum_signable_message(account));
+        let pubkey = sr_io::crypto::secp256k1_ecdsa_recover(&sig, &msg).ok()?;
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&sr_io::hashing::keccak_256(&pubkey[..])[12..]);
+        Some(address)
+    }
+
+    /// Encodes bytes as lowercase hex without a `0x` prefix
+    fn to_ascii_hex(data: &[u8]) -> Vec<u8> {
+        let to_digit = |n: u8| if n < 10 { b'0' + n } else { b'a' + n - 10 };
+        data.iter().flat_map(|b| vec![to_digit(b >> 4), to_digit(b & 0xf)]).collect()
+    }
+
     /// Helper function for executing the attestation of identities
     fn do_attest(sender: T::AccountId, identity_hash: T::Hash, attestation: Attestation) -> Result {
         let record = <IdentityOf<T>>::get(&identity_hash).ok_or("Identity does not exist")?;
         // Ensure the record is not verified
         ensure!(record.stage != IdentityStage::Verified, "Already verified");
+        // A denied identity can only be appealed
+        ensure!(record.stage != IdentityStage::Denied, "Identity denied");
         // Ensure the record isn't expired if it still exists
         ensure!(<system::Module<T>>::block_number() <= record.expiration_length, "Identity expired");
         // Check that original sender and current sender match
         ensure!(record.account == sender, "Stored identity does not match sender");
 
-        // update record
+        if record.identity_type == ETHEREUM_IDENTITY_TYPE {
+            Self::link_ethereum_address(sender.clone(), identity_hash, record, attestation)?;
+            // The signature proves the link, so no verifier is paid from the bond
+            T::Currency::unreserve(&sender, <BondOf<T>>::take(identity_hash));
+            return Ok(());
+        }
+
+        // Identities downgraded after their verification lapsed hold no bond
+        Self::reserve_bond(&sender, &identity_hash)?;
+        Self::record_attestation(sender, identity_hash, record, attestation);
+        Ok(())
+    }
+
+    /// Moves an identity to the attestation phase with a new proof
+    fn record_attestation(
+        sender: T::AccountId,
+        identity_hash: T::Hash,
+        record: IdentityRecord<T::AccountId, T::BlockNumber>,
+        attestation: Attestation,
+    ) {
         let id_type = record.identity_type.clone();
         let identity = record.identity.clone();
         let now = <system::Module<T>>::block_number();
         let expiration = now + Self::expiration_length();
+        Self::cancel_expiration(&identity_hash, record.expiration_length);
+        Self::schedule_expiration(&identity_hash, expiration);
         // Currently this implements no check against updating proof links
         <IdentityOf<T>>::insert(identity_hash, IdentityRecord {
             proof: Some(attestation.clone()),
@@ -304,13 +1236,10 @@ impl<T: Trait> Module<T> {
             ..record
         });
 
-        <IdentitiesPending<T>>::mutate(|idents| {
-            idents.retain(|(hash, _)| hash != &identity_hash);
-            idents.push((identity_hash, expiration.clone()))
-        });
+        // Votes on a previous proof do not carry over to the new one
+        <Approvals<T>>::remove(identity_hash);
 
         Self::deposit_event(RawEvent::Attest(attestation, identity_hash, sender.into(), id_type, identity));
-        Ok(())
     }
 }
 
@@ -324,28 +1253,116 @@ decl_event!(
         Attest(Attestation, Hash, AccountId, IdentityType, Identity),
         /// (record_hash, verifier, id_type, identity) when a verifier approves an account
         Verify(Hash, AccountId, IdentityType, Identity),
+        /// (record_hash, creator, address) when an Ethereum address is linked by signature
+        EthereumLinked(Hash, AccountId, Identity),
+        /// (record_hash) when a verified identity was not renewed in time and must be attested again
+        VerificationExpired(Hash),
+        /// (record_hash, owner, new_account) when the owner of an identity starts transferring it
+        TransferInitiated(Hash, AccountId, AccountId),
+        /// (record_hash, old_account, new_account) when an identity is transferred to a new account
+        IdentityTransferred(Hash, AccountId, AccountId),
+        /// (record_hash, creator) when an identity is revoked by its creator
+        Revoked(Hash, AccountId),
+        /// (record_hash) when the metadata of an identity is removed
+        MetadataCleared(Hash),
         /// (record_hash) when an account is expired and deleted
         Expired(Hash),
         /// (identity_hash, verifier, id_type, identity) when a valid verifier denies a batch of registration/attestations
         Denied(Hash, AccountId, IdentityType, Identity),
+        /// (record_hash, verifier, approve) when a verifier votes on an attestation
+        VerifierVoted(Hash, AccountId, bool),
+        /// (record_hash, creator) when the creator of a denied identity appeals
+        Appealed(Hash, AccountId),
+        /// (record_hash, approve) when an appeal is reviewed
+        AppealResolved(Hash, bool),
+        /// (identity_type) when an identity type is allowed or its rules change
+        IdentityTypeSet(IdentityType),
+        /// (identity_type) when an identity type is no longer allowed
+        IdentityTypeRemoved(IdentityType),
+        /// (verifier) when an account is added to the verifier set
+        VerifierAdded(AccountId),
+        /// (verifier) when an account is removed from the verifier set
+        VerifierRemoved(AccountId),
     }
 );
 
 decl_storage! {
     trait Store for Module<T: Trait> as Identity {
+        /// Legacy list of the hashed identities, migrated into `IdentitySet`.
+        Identities: Vec<T::Hash>;
+        /// Legacy list of pending identities and expirations, migrated into `Expirations`.
+        IdentitiesPending: Vec<(T::Hash, T::BlockNumber)>;
+        /// Whether the legacy identity lists were migrated.
+        pub IdentitiesMigrated get(fn identities_migrated) build(|_: &GenesisConfig<T>| true): bool;
         /// The hashed identities.
-        pub Identities get(fn identities): Vec<T::Hash>;
+        pub IdentitySet get(fn identity_set): linked_map T::Hash => ();
         /// Actual identity for a given hash, if it's current.
         pub IdentityOf get(fn identity_of): map T::Hash => Option<IdentityRecord<T::AccountId, T::BlockNumber>>;
-        /// List of identities awaiting attestation or verification and associated expirations
-        pub IdentitiesPending get(fn identities_pending): Vec<(T::Hash, T::BlockNumber)>;
+        /// Identities awaiting attestation, verification or renewal by the block after which they expire
+        pub Expirations get(fn expirations): map T::BlockNumber => Vec<T::Hash>;
         /// Number of blocks allowed between register/attest or attest/verify.
         pub ExpirationLength get(fn expiration_length) config(): T::BlockNumber;
+        /// Identity types that can be registered and the format of their identities
+        pub IdentityTypes get(fn identity_types) config(): map IdentityType => Option<IdentityTypeRules>;
+        /// Number of blocks a verified identity stays verified without renewal, or zero for no limit
+        pub VerificationValidity get(fn verification_validity) config(): T::BlockNumber;
+        /// Maximum length of the avatar in identity metadata
+        pub MaxAvatarLength get(fn max_avatar_length) config(): u32 = 256;
+        /// Maximum length of the display name in identity metadata
+        pub MaxDisplayNameLength get(fn max_display_name_length) config(): u32 = 64;
+        /// Maximum length of the tagline in identity metadata
+        pub MaxTaglineLength get(fn max_tagline_length) config(): u32 = 256;
+        /// Deposit reserved per byte of identity metadata
+        pub MetadataByteDeposit get(fn metadata_byte_deposit) config(): BalanceOf<T>;
+        /// Metadata deposit reserved for each identity
+        pub MetadataDeposit get(fn metadata_deposit): map T::Hash => BalanceOf<T>;
+        /// Identity of an account for each of its identity types
+        pub IdentityHashOf get(fn identity_hash_of): map (T::AccountId, IdentityType) => Option<T::Hash>;
         /// Identity types of users
         pub UsedTypes get(fn used_types): map T::AccountId => Vec<IdentityType>;
         /// Verifier set
         pub Verifiers get(fn verifiers) config(): Vec<T::AccountId>;
         /// Registration bond
         pub RegistrationBond get(fn registration_bond) config(): BalanceOf<T>;
+        /// Registration bond reserved for each identity awaiting verification
+        pub BondOf get(fn bond_of): map T::Hash => BalanceOf<T>;
+        /// Number of distinct verifiers that must agree to verify or deny an identity
+        pub VerificationQuorum get(fn verification_quorum) config(): u32;
+        /// Fee paid to the verifiers out of the registration bond upon verification
+        pub VerificationFee get(fn verification_fee) config(): BalanceOf<T>;
+        /// Share of a slashed registration bond paid to the verifiers who denied it
+        pub VerifierSlashShare get(fn verifier_slash_share) config(): Perbill;
+        /// Decision statistics of each verifier
+        pub VerifierStats get(fn verifier_stats): map T::AccountId => VerifierRecord;
+        /// Number of blocks after a denial during which it can be appealed
+        pub DisputeWindow get(fn dispute_window) config(): T::BlockNumber;
+        /// Deposit reserved from the creator of an identity to appeal its denial
+        pub AppealDeposit get(fn appeal_deposit) config(): BalanceOf<T>;
+        /// Number of blocks an appeal may stay unreviewed before the denial stands
+        pub AppealReviewPeriod get(fn appeal_review_period) config(): T::BlockNumber;
+        /// Verifiers whose votes denied an identity
+        pub DeniedBy get(fn denied_by): map T::Hash => Vec<T::AccountId>;
+        /// Appeal deposits of appealed identities
+        pub Appeals get(fn appeals): map T::Hash => Option<BalanceOf<T>>;
+        /// Accounts that identities are being transferred to
+        pub PendingTransfers get(fn pending_transfers): map T::Hash => Option<T::AccountId>;
+        /// Verifier votes on pending attestations, true for approval
+        pub Approvals get(fn approvals): map T::Hash => Vec<(T::AccountId, bool)>;
+    }
+}
+
+impl<T: Trait> IdentityProvider<T::AccountId> for Module<T> {
+    fn is_verified(who: &T::AccountId, identity_type: &[u8]) -> bool {
+        Self::identity_hash_of((who.clone(), identity_type.to_vec()))
+            .and_then(Self::identity_of)
+            .map(|record| record.stage == IdentityStage::Verified)
+            .unwrap_or(false)
+    }
+
+    fn verified_types(who: &T::AccountId) -> Vec<IdentityType> {
+        Self::used_types(who)
+            .into_iter()
+            .filter(|identity_type| Self::is_verified(who, identity_type))
+            .collect()
     }
 }
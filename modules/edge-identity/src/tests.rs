@@ -17,10 +17,12 @@
 use super::*;
 use sr_primitives::{
 	Perbill,
-	testing::Header,
-	traits::{BlakeTwo256, IdentityLookup, OnFinalize},
+	testing::{Header, TestSignature, UintAuthorityId},
+	traits::{BlakeTwo256, IdentityLookup, OnFinalize, OnInitialize},
 };
 use substrate_primitives::H256;
+use substrate_primitives::offchain::{OffchainExt, testing};
+use std::cell::RefCell;
 use support::{parameter_types, impl_outer_origin, assert_err};
 
 use support::{
@@ -86,9 +88,32 @@ impl balances::Trait for Test {
 	type CreationFee = CreationFee;
 }
 
+thread_local! {
+	static SUBMITTED: RefCell<Vec<Call<Test>>> = RefCell::new(vec![]);
+}
+
+/// Records the calls submitted by the off-chain worker instead of signing them.
+pub struct TestSubmitter;
+impl SubmitVerification<Test> for TestSubmitter {
+	fn has_verifier_key(_verifiers: Vec<u64>) -> bool {
+		true
+	}
+
+	fn submit_from_verifier(call: Call<Test>, _verifiers: Vec<u64>) -> bool {
+		SUBMITTED.with(|submitted| submitted.borrow_mut().push(call));
+		true
+	}
+}
+
 impl Trait for Test {
 	type Event = ();
 	type Currency = balances::Module<Self>;
+	type VerifierOrigin = system::EnsureRoot<u64>;
+	type Slashed = ();
+	type Call = Call<Test>;
+	type SubmitTransaction = TestSubmitter;
+	type StatementSignature = TestSignature;
+	type StatementSigner = UintAuthorityId;
 }
 
 type Balances = balances::Module<Test>;
@@ -96,6 +121,13 @@ type System = system::Module<Test>;
 type Identity = Module<Test>;
 
 const BOND: u128 = 10;
+const APPEAL_DEPOSIT: u128 = 5;
+const DISPUTE_WINDOW: u64 = 100;
+const APPEAL_REVIEW_PERIOD: u64 = 200;
+fn handle_rules(max_length: u32) -> IdentityTypeRules {
+	IdentityTypeRules { min_length: 1, max_length, charset: IdentityCharset::Handle }
+}
+
 // This function basically just builds a genesis storage key/value store according to
 // our desired mockup.
 fn new_test_ext() -> sr_io::TestExternalities {
@@ -106,6 +138,23 @@ fn new_test_ext() -> sr_io::TestExternalities {
 			expiration_length: 10000,
 			verifiers: vec![1_u64],
 			registration_bond: BOND,
+			verification_quorum: 1,
+			verification_fee: 0,
+			verifier_slash_share: Perbill::from_percent(50),
+			dispute_window: DISPUTE_WINDOW,
+			appeal_deposit: APPEAL_DEPOSIT,
+			appeal_review_period: APPEAL_REVIEW_PERIOD,
+			verification_validity: 0,
+			max_avatar_length: 64,
+			max_display_name_length: 16,
+			max_tagline_length: 32,
+			metadata_byte_deposit: 1,
+			identity_types: vec![
+				(b"github".to_vec(), handle_rules(39)),
+				(b"twitter".to_vec(), handle_rules(15)),
+				(b"email".to_vec(), IdentityTypeRules { min_length: 3, max_length: 254, charset: IdentityCharset::Printable }),
+				(b"ethereum".to_vec(), IdentityTypeRules { min_length: 40, max_length: 40, charset: IdentityCharset::Hex }),
+			],
 		}.build_storage().unwrap().0,
 	);
 	t.0.extend(
@@ -134,12 +183,12 @@ fn register_and_attest(who: u64, identity_type: &[u8], identity: &[u8], attestat
 	Identity::register_and_attest(Origin::signed(who), identity_type.to_vec(), identity.to_vec(), attestation.to_vec())
 }
 
-fn verify_identity(who: u64, identity_hash: H256, verifier_index: u32) -> Result {
-	Identity::verify(Origin::signed(who), identity_hash, verifier_index)
+fn verify_identity(who: u64, identity_hash: H256) -> Result {
+	Identity::verify(Origin::signed(who), identity_hash)
 }
 
-fn deny_many(who: u64, identity_hashes: &[H256], verifier_index: u32) -> Result {
-	Identity::deny_many(Origin::signed(who), identity_hashes.to_vec(), verifier_index)
+fn deny_many(who: u64, identity_hashes: &[H256]) -> Result {
+	Identity::deny_many(Origin::signed(who), identity_hashes.to_vec())
 }
 
 fn revoke(who: u64, identity_hash: H256) -> Result {
@@ -200,7 +249,7 @@ fn register_should_work() {
 		let after_register_balance = Balances::free_balance(public);
 		assert_eq!(balance - BOND, after_register_balance);
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(default_identity_record(public, identity_type, identity))
@@ -223,7 +272,7 @@ fn register_twice_should_not_work() {
 			"Identity type already used"
 		);
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(default_identity_record(public, identity_type, identity))
@@ -247,7 +296,7 @@ fn register_existing_identity_should_not_work() {
 			"Identity already exists"
 		);
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(default_identity_record(public, identity_type, identity))
@@ -272,7 +321,7 @@ fn register_same_type_should_not_work() {
 			"Identity type already used"
 		);
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(default_identity_record(public, identity_type, identity))
@@ -303,7 +352,7 @@ fn register_and_attest_should_work() {
 		now = System::block_number();
 		let _attest_expires_at = now + expiration_length;
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(IdentityRecord {
@@ -338,7 +387,7 @@ fn register_and_attest_as_one_should_work() {
 		let _attest_expires_at = now + expiration_length;
 
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(IdentityRecord {
@@ -365,7 +414,7 @@ fn attest_without_register_should_not_work() {
 			"Identity does not exist"
 		);
 		assert_eq!(Identity::identities(), vec![]);
-		assert_eq!(Identity::identities_pending(), vec![]);
+		assert_eq!(Identity::expirations(10001), vec![]);
 		assert_eq!(Identity::identity_of(identity_hash), None);
 	});
 }
@@ -388,7 +437,7 @@ fn attest_from_different_account_should_not_work() {
 			"Stored identity does not match sender"
 		);
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(default_identity_record(public, identity_type, identity))
@@ -426,12 +475,12 @@ fn register_attest_and_verify_should_work() {
 
 		System::set_block_number(2);
 		let verifier = 1_u64;
-		assert_ok!(verify_identity(verifier, identity_hash, 0));
+		assert_ok!(verify_identity(verifier, identity_hash));
 		let balance_after_verify = Balances::free_balance(public);
 		assert_eq!(balance, balance_after_verify);
 
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![]);
+		assert_eq!(Identity::expirations(10001), vec![]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(IdentityRecord {
@@ -450,10 +499,10 @@ fn deny_many_should_work() {
 		System::set_block_number(1);
 		let mut id_hashes = vec![];
 		let _test_id_type: &[u8] = b"github";
-		let _test_id: Vec<u8> = "drewstone 4".as_bytes().to_vec();
+		let _test_id: Vec<u8> = "drewstone-4".as_bytes().to_vec();
 		for i in 1..5 {
 			let identity_type: &[u8] = b"github";
-			let identity: Vec<u8> = format!("drewstone {}", i).as_bytes().to_vec();
+			let identity: Vec<u8> = format!("drewstone-{}", i).as_bytes().to_vec();
 			let identity_hash = build_identity_hash(identity_type, &identity);	
 			assert_ok!(register_identity(i as u64, identity_type, &identity));
 			let attestation: &[u8] = b"this_is_a_fake_attestation";
@@ -462,7 +511,7 @@ fn deny_many_should_work() {
 		}
 
 		let verifier = 1_u64;
-		assert_ok!(deny_many(verifier, &id_hashes, 0));
+		assert_ok!(deny_many(verifier, &id_hashes));
 	});
 }
 
@@ -482,13 +531,13 @@ fn attest_after_verify_should_not_work() {
 		assert_ok!(attest_to_identity(public, identity_hash, attestation));
 
 		let verifier = 1_u64;
-		assert_ok!(verify_identity(verifier, identity_hash, 0));
+		assert_ok!(verify_identity(verifier, identity_hash));
 		assert_err!(
 			attest_to_identity(public, identity_hash, attestation),
 			"Already verified"
 		);
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![]);
+		assert_eq!(Identity::expirations(10001), vec![]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(IdentityRecord {
@@ -517,11 +566,11 @@ fn verify_from_nonverifier_should_not_work() {
 		assert_ok!(attest_to_identity(public, identity_hash, attestation));
 
 		assert_err!(
-			verify_identity(public, identity_hash, 0),
+			verify_identity(public, identity_hash),
 			"Sender is not a verifier"
 		);
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(IdentityRecord {
@@ -534,34 +583,61 @@ fn verify_from_nonverifier_should_not_work() {
 }
 
 #[test]
-fn verify_from_wrong_index_should_not_work() {
+fn verify_from_added_verifier_should_work() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 		let identity_type: &[u8] = b"github";
 		let identity: &[u8] = b"drewstone";
 		let identity_hash = build_identity_hash(identity_type, identity);
 
-		let public = 1_u64;
+		let public = 2_u64;
+		let verifier = 3_u64;
 
 		assert_ok!(register_identity(public, identity_type, identity));
 
 		let attestation: &[u8] = b"www.proof.com/attest_of_extra_proof";
 		assert_ok!(attest_to_identity(public, identity_hash, attestation));
 
-		assert_err!(
-			verify_identity(public, identity_hash, 1),
-			"Verifier index out of bounds"
-		);
-		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
-		assert_eq!(
-			Identity::identity_of(identity_hash),
-			Some(IdentityRecord {
-				stage: IdentityStage::Attested,
-				proof: Some(attestation.to_vec()),
-				..default_identity_record(public, identity_type, identity)
-			})
-		);
+		assert_err!(verify_identity(verifier, identity_hash), "Sender is not a verifier");
+		assert_ok!(Identity::add_verifier(Origin::ROOT, verifier));
+		assert_eq!(Identity::verifiers(), vec![1_u64, verifier]);
+		assert_ok!(verify_identity(verifier, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Verified);
+	});
+}
+
+#[test]
+fn manage_verifiers_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert!(Identity::add_verifier(Origin::signed(1), 2).is_err());
+		assert_err!(Identity::add_verifier(Origin::ROOT, 1), "Account is already a verifier");
+		assert_ok!(Identity::add_verifier(Origin::ROOT, 2));
+		assert_ok!(Identity::remove_verifier(Origin::ROOT, 1));
+		assert_eq!(Identity::verifiers(), vec![2_u64]);
+		assert_err!(Identity::remove_verifier(Origin::ROOT, 1), "Account is not a verifier");
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![4, 3, 4]));
+		assert_eq!(Identity::verifiers(), vec![3_u64, 4]);
+		assert!(!Identity::is_verifier(&2));
+	});
+}
+
+#[test]
+fn removed_verifier_should_not_verify() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let identity_type: &[u8] = b"github";
+		let identity: &[u8] = b"drewstone";
+		let identity_hash = build_identity_hash(identity_type, identity);
+		let public = 2_u64;
+
+		assert_ok!(register_identity(public, identity_type, identity));
+		let attestation: &[u8] = b"www.proof.com/attest_of_extra_proof";
+		assert_ok!(attest_to_identity(public, identity_hash, attestation));
+
+		assert_ok!(Identity::add_verifier(Origin::ROOT, 3));
+		assert_ok!(Identity::remove_verifier(Origin::ROOT, 1));
+		assert_err!(verify_identity(1, identity_hash), "Sender is not a verifier");
 	});
 }
 
@@ -592,7 +668,7 @@ fn register_should_expire() {
 		);
 
 		assert_eq!(Identity::identities(), vec![]);
-		assert_eq!(Identity::identities_pending(), vec![]);
+		assert_eq!(Identity::expirations(10001), vec![]);
 		assert_eq!(Identity::identity_of(identity_hash), None);
 	});
 }
@@ -620,7 +696,7 @@ fn add_metadata_should_work() {
 			tagline
 		));
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		let default_record = default_identity_record(public, identity_type, identity);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
@@ -653,7 +729,7 @@ fn add_metadata_without_register_should_not_work() {
 			"Identity does not exist"
 		);
 		assert_eq!(Identity::identities(), vec![]);
-		assert_eq!(Identity::identities_pending(), vec![]);
+		assert_eq!(Identity::expirations(10001), vec![]);
 		assert_eq!(Identity::identity_of(identity_hash), None);
 	});
 }
@@ -679,7 +755,7 @@ fn add_metadata_from_different_account_should_not_work() {
 			"Stored identity does not match sender"
 		);
 		assert_eq!(Identity::identities(), vec![identity_hash]);
-		assert_eq!(Identity::identities_pending(), vec![(identity_hash, 10001)]);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(default_identity_record(public, identity_type, identity))
@@ -703,7 +779,7 @@ fn revoke_verified_should_work() {
 
 		System::set_block_number(2);
 		let verifier = 1_u64;
-		assert_ok!(verify_identity(verifier, identity_hash, 0));
+		assert_ok!(verify_identity(verifier, identity_hash));
 		assert_eq!(
 			Identity::identity_of(identity_hash),
 			Some(IdentityRecord {
@@ -737,7 +813,7 @@ fn revoke_from_wrong_sender_should_not_work() {
 
 		System::set_block_number(2);
 		let verifier = 1_u64;
-		assert_ok!(verify_identity(verifier, identity_hash, 0));
+		assert_ok!(verify_identity(verifier, identity_hash));
 		assert_err!(revoke(2_u64, identity_hash), "Stored identity does not match sender");
 	});
 }
@@ -810,4 +886,1115 @@ fn register_should_expire_and_work_again() {
 		assert_eq!(Identity::identity_of(identity_hash), None);
 		assert_ok!(register_identity(public, identity_type, identity));
 	});
-}
\ No newline at end of file
+}
+
+fn submitted_calls() -> Vec<Call<Test>> {
+	SUBMITTED.with(|submitted| submitted.borrow().clone())
+}
+
+fn signed_statement(signer: u64, account: u64, identity_type: &[u8], identity: &[u8]) -> Vec<u8> {
+	let message = Identity::attestation_message(&account, identity_type, identity);
+	let signature = TestSignature(signer, message).encode();
+	let hex: String = signature.iter().map(|b| format!("{:02x}", b)).collect();
+	format!(
+		"Linking my Edgeware account\n{}{}\n",
+		std::str::from_utf8(STATEMENT_PREFIX).unwrap(),
+		hex,
+	).into_bytes()
+}
+
+fn expect_proof_request(state: &mut testing::OffchainState, uri: &[u8], body: Vec<u8>) {
+	state.expect_request(0, testing::PendingRequest {
+		method: "GET".into(),
+		uri: String::from_utf8(uri.to_vec()).unwrap(),
+		response: Some(body),
+		sent: true,
+		..Default::default()
+	});
+}
+
+#[test]
+fn offchain_worker_should_verify_signed_statement() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let mut t = new_test_ext();
+	t.register_extension(OffchainExt::new(offchain));
+	t.execute_with(|| {
+		System::set_block_number(1);
+		let identity_type: &[u8] = b"github";
+		let identity: &[u8] = b"drewstone";
+		let identity_hash = build_identity_hash(identity_type, identity);
+		let public = 2_u64;
+		let attestation: &[u8] = b"https://gist.githubusercontent.com/drewstone/abc/raw/proof.txt";
+		assert_ok!(register_and_attest(public, identity_type, identity, attestation));
+
+		expect_proof_request(
+			&mut state.write(),
+			attestation,
+			signed_statement(public, public, identity_type, identity),
+		);
+		Identity::verify_pending_attestations();
+		assert_eq!(submitted_calls(), vec![Call::verify(identity_hash)]);
+
+		// an attestation is only submitted once
+		Identity::verify_pending_attestations();
+		assert_eq!(submitted_calls().len(), 1);
+	});
+}
+
+#[test]
+fn offchain_worker_should_deny_statement_from_other_account() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let mut t = new_test_ext();
+	t.register_extension(OffchainExt::new(offchain));
+	t.execute_with(|| {
+		System::set_block_number(1);
+		let identity_type: &[u8] = b"github";
+		let identity: &[u8] = b"drewstone";
+		let identity_hash = build_identity_hash(identity_type, identity);
+		let public = 2_u64;
+		let attestation: &[u8] = b"https://gist.github.com/drewstone/abc";
+		assert_ok!(register_and_attest(public, identity_type, identity, attestation));
+
+		expect_proof_request(
+			&mut state.write(),
+			attestation,
+			signed_statement(3, public, identity_type, identity),
+		);
+		Identity::verify_pending_attestations();
+		assert_eq!(submitted_calls(), vec![Call::deny(identity_hash)]);
+	});
+}
+
+#[test]
+fn offchain_worker_should_deny_missing_statement() {
+	let (offchain, state) = testing::TestOffchainExt::new();
+	let mut t = new_test_ext();
+	t.register_extension(OffchainExt::new(offchain));
+	t.execute_with(|| {
+		System::set_block_number(1);
+		let identity_type: &[u8] = b"twitter";
+		let identity: &[u8] = b"drewstone";
+		let identity_hash = build_identity_hash(identity_type, identity);
+		let public = 2_u64;
+		let attestation: &[u8] = b"https://twitter.com/drewstone/status/1";
+		assert_ok!(register_and_attest(public, identity_type, identity, attestation));
+
+		expect_proof_request(&mut state.write(), attestation, b"nothing to see here".to_vec());
+		Identity::verify_pending_attestations();
+		assert_eq!(submitted_calls(), vec![Call::deny(identity_hash)]);
+	});
+}
+
+#[test]
+fn offchain_worker_should_deny_proof_hosted_elsewhere() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let identity_type: &[u8] = b"github";
+		let identity: &[u8] = b"drewstone";
+		let identity_hash = build_identity_hash(identity_type, identity);
+		let public = 2_u64;
+		let attestation: &[u8] = b"https://gist.github.com/someoneelse/abc";
+		assert_ok!(register_and_attest(public, identity_type, identity, attestation));
+
+		Identity::verify_pending_attestations();
+		assert_eq!(submitted_calls(), vec![Call::deny(identity_hash)]);
+	});
+}
+
+#[test]
+fn offchain_worker_should_skip_unsupported_types() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let identity_type: &[u8] = b"email";
+		let identity: &[u8] = b"drew@commonwealth.im";
+		let public = 2_u64;
+		let attestation: &[u8] = b"https://commonwealth.im/proof";
+		assert_ok!(register_and_attest(public, identity_type, identity, attestation));
+
+		Identity::verify_pending_attestations();
+		assert_eq!(submitted_calls(), vec![]);
+	});
+}
+
+fn register_and_attest_github(public: u64) -> H256 {
+	let identity_type: &[u8] = b"github";
+	let identity: &[u8] = b"drewstone";
+	let attestation: &[u8] = b"www.proof.com/attest_of_extra_proof";
+	assert_ok!(register_and_attest(public, identity_type, identity, attestation));
+	build_identity_hash(identity_type, identity)
+}
+
+fn deny_identity(who: u64, identity_hash: H256) -> Result {
+	Identity::deny(Origin::signed(who), identity_hash)
+}
+
+#[test]
+fn verify_with_quorum_should_wait_for_enough_approvals() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3, 4]));
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 2));
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_eq!(Identity::approvals(identity_hash), vec![(1, true)]);
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Attested);
+		assert_err!(verify_identity(1, identity_hash), "Verifier already voted");
+
+		assert_ok!(deny_identity(3, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Attested);
+
+		assert_ok!(verify_identity(4, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Verified);
+		assert_eq!(Identity::approvals(identity_hash), vec![]);
+		assert_eq!(Balances::free_balance(public), 100);
+	});
+}
+
+fn appeal(who: u64, identity_hash: H256) -> Result {
+	Identity::appeal(Origin::signed(who), identity_hash)
+}
+
+fn end_dispute_window() {
+	let end = System::block_number() + DISPUTE_WINDOW + 1;
+	System::set_block_number(end);
+	<Identity as OnFinalize<u64>>::on_finalize(end);
+}
+
+#[test]
+fn deny_with_quorum_should_slash_once_agreed() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3, 4]));
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 2));
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+
+		assert_ok!(deny_identity(1, identity_hash));
+		assert!(Identity::identity_of(identity_hash).is_some());
+		assert_ok!(deny_identity(4, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Denied);
+		assert_eq!(Identity::approvals(identity_hash), vec![]);
+		assert_eq!(Balances::reserved_balance(public), BOND);
+
+		end_dispute_window();
+		assert_eq!(Identity::identity_of(identity_hash), None);
+		assert_eq!(Balances::reserved_balance(public), 0);
+		assert_eq!(Balances::free_balance(public), 100 - BOND);
+	});
+}
+
+#[test]
+fn reattest_should_reset_approvals() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3]));
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 2));
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_ok!(attest_to_identity(public, identity_hash, b"www.proof.com/new_proof"));
+		assert_eq!(Identity::approvals(identity_hash), vec![]);
+	});
+}
+
+#[test]
+fn set_verification_quorum_should_be_bounded() {
+	new_test_ext().execute_with(|| {
+		assert!(Identity::set_verification_quorum(Origin::signed(1), 1).is_err());
+		assert_err!(Identity::set_verification_quorum(Origin::ROOT, 0), "Quorum must be positive");
+		assert_err!(Identity::set_verification_quorum(Origin::ROOT, 2), "Quorum exceeds number of verifiers");
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 1));
+	});
+}
+
+#[test]
+fn verify_should_pay_fee_to_agreeing_verifiers() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		<VerificationFee<Test>>::put(4);
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3, 4]));
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 2));
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+
+		assert_ok!(deny_identity(3, identity_hash));
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_ok!(verify_identity(4, identity_hash));
+		assert_eq!(Balances::free_balance(public), 100 - 4);
+		assert_eq!(Balances::reserved_balance(public), 0);
+		assert_eq!(Balances::free_balance(1), 102);
+		assert_eq!(Balances::free_balance(4), 102);
+		assert_eq!(Balances::free_balance(3), 100);
+
+		assert_eq!(Identity::verifier_stats(1), VerifierRecord { verified: 1, denied: 0, overturned: 0 });
+		assert_eq!(Identity::verifier_stats(3), VerifierRecord { verified: 0, denied: 0, overturned: 1 });
+	});
+}
+
+#[test]
+fn deny_should_share_slashed_bond_with_verifiers() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3, 4]));
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 2));
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+
+		assert_ok!(verify_identity(3, identity_hash));
+		assert_ok!(deny_identity(1, identity_hash));
+		assert_ok!(deny_identity(4, identity_hash));
+		end_dispute_window();
+		assert_eq!(Balances::free_balance(public), 100 - BOND);
+		// Half of the bond is split between the denying verifiers
+		assert_eq!(Balances::free_balance(1), 100 + BOND / 4);
+		assert_eq!(Balances::free_balance(4), 100 + BOND / 4);
+		assert_eq!(Balances::free_balance(3), 100);
+
+		assert_eq!(Identity::verifier_stats(4), VerifierRecord { verified: 0, denied: 1, overturned: 0 });
+		assert_eq!(Identity::verifier_stats(3), VerifierRecord { verified: 0, denied: 0, overturned: 1 });
+	});
+}
+
+#[test]
+fn remove_overturned_verifiers_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3, 4]));
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 2));
+		let identity_hash = register_and_attest_github(2);
+		assert_ok!(deny_identity(3, identity_hash));
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_ok!(verify_identity(4, identity_hash));
+
+		assert!(Identity::remove_overturned_verifiers(Origin::signed(1), 0).is_err());
+		assert_ok!(Identity::remove_overturned_verifiers(Origin::ROOT, 1));
+		assert_eq!(Identity::verifiers(), vec![1, 3, 4]);
+		assert_ok!(Identity::remove_overturned_verifiers(Origin::ROOT, 0));
+		assert_eq!(Identity::verifiers(), vec![1, 4]);
+	});
+}
+
+#[test]
+fn verifier_set_should_not_shrink_below_quorum() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3, 4]));
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 3));
+		assert_err!(Identity::remove_verifier(Origin::ROOT, 4), "Too few verifiers for the verification quorum");
+		assert_err!(Identity::set_verifiers(Origin::ROOT, vec![1, 3]), "Too few verifiers for the verification quorum");
+
+		let identity_hash = register_and_attest_github(2);
+		assert_ok!(deny_identity(3, identity_hash));
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_ok!(verify_identity(4, identity_hash));
+		assert_err!(
+			Identity::remove_overturned_verifiers(Origin::ROOT, 0),
+			"Too few verifiers for the verification quorum"
+		);
+		assert_eq!(Identity::verifiers(), vec![1, 3, 4]);
+	});
+}
+
+#[test]
+fn removed_verifier_votes_should_not_count() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3, 4]));
+		assert_ok!(Identity::set_verification_quorum(Origin::ROOT, 2));
+		let identity_hash = register_and_attest_github(2);
+
+		assert_ok!(verify_identity(3, identity_hash));
+		assert_ok!(Identity::remove_verifier(Origin::ROOT, 3));
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Attested);
+		assert_eq!(Identity::approvals(identity_hash), vec![(1, true)]);
+
+		assert_ok!(verify_identity(4, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Verified);
+		assert_eq!(Identity::verifier_stats(3), VerifierRecord::default());
+	});
+}
+
+fn register_attest_and_deny(public: u64) -> H256 {
+	System::set_block_number(1);
+	assert_ok!(Identity::set_verifiers(Origin::ROOT, vec![1, 3]));
+	let identity_hash = register_and_attest_github(public);
+	assert_ok!(deny_identity(1, identity_hash));
+	identity_hash
+}
+
+#[test]
+fn denied_identity_should_only_be_appealed() {
+	new_test_ext().execute_with(|| {
+		let public = 2_u64;
+		let identity_hash = register_attest_and_deny(public);
+
+		assert_err!(verify_identity(3, identity_hash), "Identity denied");
+		assert_err!(attest_to_identity(public, identity_hash, b"www.proof.com/new_proof"), "Identity denied");
+		assert_err!(Identity::revoke(Origin::signed(public), identity_hash), "Identity denied");
+		assert_err!(appeal(4, identity_hash), "Stored identity does not match sender");
+		assert_ok!(appeal(public, identity_hash));
+		assert_err!(appeal(public, identity_hash), "Identity already appealed");
+		assert_eq!(Balances::reserved_balance(public), BOND + APPEAL_DEPOSIT);
+	});
+}
+
+#[test]
+fn appeal_after_dispute_window_should_not_work() {
+	new_test_ext().execute_with(|| {
+		let public = 2_u64;
+		let identity_hash = register_attest_and_deny(public);
+		System::set_block_number(1 + DISPUTE_WINDOW + 1);
+		assert_err!(appeal(public, identity_hash), "Dispute window expired");
+	});
+}
+
+#[test]
+fn appealed_identity_should_not_expire_with_dispute_window() {
+	new_test_ext().execute_with(|| {
+		let public = 2_u64;
+		let identity_hash = register_attest_and_deny(public);
+		assert_ok!(appeal(public, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().expiration_length, 1 + APPEAL_REVIEW_PERIOD);
+		end_dispute_window();
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Denied);
+		assert_eq!(Balances::reserved_balance(public), BOND + APPEAL_DEPOSIT);
+	});
+}
+
+#[test]
+fn unreviewed_appeal_should_let_denial_stand() {
+	new_test_ext().execute_with(|| {
+		let public = 2_u64;
+		let identity_hash = register_attest_and_deny(public);
+		assert_ok!(appeal(public, identity_hash));
+
+		let deadline = 1 + APPEAL_REVIEW_PERIOD;
+		System::set_block_number(deadline + 1);
+		<Identity as OnFinalize<u64>>::on_finalize(deadline + 1);
+		assert_eq!(Identity::identity_of(identity_hash), None);
+		assert_eq!(Identity::appeals(identity_hash), None);
+		assert_eq!(Balances::reserved_balance(public), 0);
+		assert_eq!(Balances::free_balance(public), 100 - BOND);
+		assert_eq!(Balances::free_balance(1), 100 + BOND / 2);
+	});
+}
+
+#[test]
+fn accepted_appeal_should_restore_bond_and_verify() {
+	new_test_ext().execute_with(|| {
+		let public = 2_u64;
+		let identity_hash = register_attest_and_deny(public);
+		assert_ok!(appeal(public, identity_hash));
+
+		assert_err!(Identity::review_appeal(Origin::signed(1), identity_hash, true), "Verifier denied this identity");
+		assert_err!(Identity::review_appeal(Origin::signed(4), identity_hash, true), "Sender is not a verifier");
+		assert_ok!(Identity::review_appeal(Origin::signed(3), identity_hash, true));
+
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Verified);
+		assert_eq!(Balances::free_balance(public), 100);
+		assert_eq!(Identity::verifier_stats(1).overturned, 1);
+		assert_eq!(Identity::appeals(identity_hash), None);
+		assert_eq!(Identity::denied_by(identity_hash), vec![]);
+	});
+}
+
+#[test]
+fn rejected_appeal_should_slash_bond_and_deposit() {
+	new_test_ext().execute_with(|| {
+		let public = 2_u64;
+		let identity_hash = register_attest_and_deny(public);
+		assert_ok!(appeal(public, identity_hash));
+
+		assert!(Identity::force_review_appeal(Origin::signed(3), identity_hash, false).is_err());
+		assert_ok!(Identity::force_review_appeal(Origin::ROOT, identity_hash, false));
+
+		assert_eq!(Identity::identity_of(identity_hash), None);
+		assert_eq!(Balances::free_balance(public), 100 - BOND - APPEAL_DEPOSIT);
+		assert_eq!(Balances::reserved_balance(public), 0);
+		assert_eq!(Balances::free_balance(1), 100 + BOND / 2);
+		assert_eq!(Identity::used_types(public), Vec::<Vec<u8>>::new());
+		assert_err!(Identity::force_review_appeal(Origin::ROOT, identity_hash, true), "Identity does not exist");
+	});
+}
+
+#[test]
+fn register_should_require_allowed_identity_type() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_err!(register_identity(1, b"myspace", b"drewstone"), "Identity type not allowed");
+		assert_ok!(register_identity(1, b"github", b"drewstone"));
+	});
+}
+
+#[test]
+fn register_should_validate_identity_format() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert_err!(register_identity(1, b"github", b""), "Identity does not match identity type");
+		assert_err!(register_identity(1, b"github", b"drew stone"), "Identity does not match identity type");
+		assert_err!(register_identity(1, b"twitter", b"a_very_long_twitter_handle"), "Identity does not match identity type");
+		assert_err!(
+			register_and_attest(1, b"github", b"drew/stone", b"www.proof.com/attest_of_extra_proof"),
+			"Identity does not match identity type"
+		);
+		assert_eq!(Identity::identities(), vec![]);
+		assert_ok!(register_identity(1, b"twitter", b"drew_stone"));
+	});
+}
+
+#[test]
+fn identity_types_should_be_managed_by_governance() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let rules = IdentityTypeRules { min_length: 40, max_length: 40, charset: IdentityCharset::Hex };
+		assert!(Identity::set_identity_type(Origin::signed(1), b"ethereum".to_vec(), rules).is_err());
+		assert_err!(
+			Identity::set_identity_type(Origin::ROOT, b"ethereum".to_vec(), IdentityTypeRules { max_length: 0, ..rules }),
+			"Maximum identity length must be positive"
+		);
+		assert_err!(
+			Identity::set_identity_type(Origin::ROOT, b"ethereum".to_vec(), IdentityTypeRules { min_length: 41, ..rules }),
+			"Minimum identity length exceeds maximum"
+		);
+		assert_ok!(Identity::set_identity_type(Origin::ROOT, b"ethereum".to_vec(), rules));
+		assert_eq!(Identity::identity_types(b"ethereum".to_vec()), Some(rules));
+		assert_err!(register_identity(1, b"ethereum", b"0xDEADBEEF"), "Identity does not match identity type");
+		assert_err!(register_identity(1, b"ethereum", b"deadbeef"), "Identity does not match identity type");
+		assert_ok!(register_identity(1, b"ethereum", b"deadbeefdeadbeefdeadbeefdeadbeefdeadbeef"));
+
+		assert_ok!(Identity::remove_identity_type(Origin::ROOT, b"github".to_vec()));
+		assert_err!(Identity::remove_identity_type(Origin::ROOT, b"github".to_vec()), "Identity type not allowed");
+		assert_err!(register_identity(1, b"github", b"drewstone"), "Identity type not allowed");
+	});
+}
+
+fn ethereum_secret(seed: &[u8]) -> secp256k1::SecretKey {
+	secp256k1::SecretKey::parse(&sr_io::hashing::keccak_256(seed)).unwrap()
+}
+
+fn ethereum_address(secret: &secp256k1::SecretKey) -> Vec<u8> {
+	let public = secp256k1::PublicKey::from_secret_key(secret);
+	let hash = sr_io::hashing::keccak_256(&public.serialize()[1..65]);
+	hash[12..].iter().map(|b| format!("{:02x}", b)).collect::<String>().into_bytes()
+}
+
+fn ethereum_signature(secret: &secp256k1::SecretKey, account: u64) -> Vec<u8> {
+	let msg = sr_io::hashing::keccak_256(&Identity::ethereum_signable_message(&account));
+	let (sig, recovery_id) = secp256k1::sign(&secp256k1::Message::parse(&msg), secret);
+	let mut signature = sig.serialize().to_vec();
+	signature.push(recovery_id.serialize());
+	signature
+}
+
+#[test]
+fn ethereum_signature_should_verify_identity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 2_u64;
+		let secret = ethereum_secret(b"Alice");
+		let address = ethereum_address(&secret);
+		let identity_hash = build_identity_hash(b"ethereum", &address);
+
+		assert_ok!(register_and_attest(public, b"ethereum", &address, &ethereum_signature(&secret, public)));
+		let record = Identity::identity_of(identity_hash).unwrap();
+		assert_eq!(record.stage, IdentityStage::Verified);
+		assert_eq!(record.expiration_length, 0);
+		assert_eq!(Identity::expirations(10001), vec![]);
+		assert_eq!(Balances::free_balance(public), 100);
+	});
+}
+
+#[test]
+fn ethereum_signature_from_other_address_should_not_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 2_u64;
+		let address = ethereum_address(&ethereum_secret(b"Alice"));
+		let identity_hash = build_identity_hash(b"ethereum", &address);
+		assert_ok!(register_identity(public, b"ethereum", &address));
+
+		let other_signature = ethereum_signature(&ethereum_secret(b"Bob"), public);
+		assert_err!(
+			attest_to_identity(public, identity_hash, &other_signature),
+			"Ethereum signature does not match identity"
+		);
+		// A signature for another account recovers a different address
+		let wrong_account = ethereum_signature(&ethereum_secret(b"Alice"), 3);
+		assert_err!(
+			attest_to_identity(public, identity_hash, &wrong_account),
+			"Ethereum signature does not match identity"
+		);
+		assert_err!(attest_to_identity(public, identity_hash, b"not a signature"), "Invalid Ethereum signature");
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Registered);
+	});
+}
+
+fn finalize_at(n: u64) {
+	System::set_block_number(n);
+	<Identity as OnFinalize<u64>>::on_finalize(n);
+}
+
+#[test]
+fn verified_identity_without_validity_should_not_expire() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let identity_hash = register_and_attest_github(2);
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_eq!(Identity::expirations(10001), vec![]);
+		finalize_at(100000);
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Verified);
+	});
+}
+
+#[test]
+fn verified_identity_should_downgrade_after_validity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		<VerificationValidity<Test>>::put(50);
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().expiration_length, 51);
+		assert_eq!(Identity::expirations(51), vec![identity_hash]);
+
+		finalize_at(51);
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Verified);
+		finalize_at(52);
+		let record = Identity::identity_of(identity_hash).unwrap();
+		assert_eq!(record.stage, IdentityStage::Registered);
+		assert_eq!(record.proof, None);
+		assert_eq!(Balances::reserved_balance(public), 0);
+		assert_eq!(Identity::expirations(52 + 10000), vec![identity_hash]);
+
+		// The downgraded identity is verified again through a new attestation
+		assert_ok!(attest_to_identity(public, identity_hash, b"www.proof.com/new_proof"));
+		assert_eq!(Balances::reserved_balance(public), BOND);
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Verified);
+		assert_eq!(Balances::reserved_balance(public), 0);
+	});
+}
+
+#[test]
+fn downgrade_should_not_reserve_bond() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		<VerificationValidity<Test>>::put(50);
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_ok!(Balances::transfer(Origin::signed(public), 3, 100 - BOND / 2));
+
+		finalize_at(52);
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Registered);
+		assert_eq!(Identity::used_types(public), vec![b"github".to_vec()]);
+		assert_err!(
+			attest_to_identity(public, identity_hash, b"www.proof.com/new_proof"),
+			"Not enough currency for reserve bond"
+		);
+	});
+}
+
+#[test]
+fn expired_downgrade_should_refund_bond() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		<VerificationValidity<Test>>::put(50);
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_ok!(verify_identity(1, identity_hash));
+		finalize_at(52);
+		assert_ok!(attest_to_identity(public, identity_hash, b"www.proof.com/new_proof"));
+		assert_eq!(Balances::reserved_balance(public), BOND);
+
+		finalize_at(52 + 10001);
+		assert_eq!(Identity::identity_of(identity_hash), None);
+		assert_eq!(Balances::reserved_balance(public), 0);
+		assert_eq!(Balances::free_balance(public), 100);
+	});
+}
+
+#[test]
+fn renew_should_reenter_attestation() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		<VerificationValidity<Test>>::put(50);
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_err!(
+			Identity::renew(Origin::signed(public), identity_hash, b"www.proof.com/new_proof".to_vec()),
+			"Identity not verified"
+		);
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_err!(
+			Identity::renew(Origin::signed(3), identity_hash, b"www.proof.com/new_proof".to_vec()),
+			"Stored identity does not match sender"
+		);
+
+		System::set_block_number(40);
+		assert_ok!(Identity::renew(Origin::signed(public), identity_hash, b"www.proof.com/new_proof".to_vec()));
+		let record = Identity::identity_of(identity_hash).unwrap();
+		assert_eq!(record.stage, IdentityStage::Attested);
+		assert_eq!(record.proof, Some(b"www.proof.com/new_proof".to_vec()));
+		assert_eq!(Balances::reserved_balance(public), BOND);
+
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().expiration_length, 90);
+		assert_eq!(Balances::reserved_balance(public), 0);
+	});
+}
+
+#[test]
+fn renew_ethereum_identity_should_require_new_signature() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		<VerificationValidity<Test>>::put(50);
+		let public = 2_u64;
+		let secret = ethereum_secret(b"Alice");
+		let address = ethereum_address(&secret);
+		let identity_hash = build_identity_hash(b"ethereum", &address);
+		assert_ok!(register_and_attest(public, b"ethereum", &address, &ethereum_signature(&secret, public)));
+
+		System::set_block_number(40);
+		assert_err!(
+			Identity::renew(Origin::signed(public), identity_hash, ethereum_signature(&ethereum_secret(b"Bob"), public)),
+			"Ethereum signature does not match identity"
+		);
+		assert_ok!(Identity::renew(Origin::signed(public), identity_hash, ethereum_signature(&secret, public)));
+		let record = Identity::identity_of(identity_hash).unwrap();
+		assert_eq!(record.stage, IdentityStage::Verified);
+		assert_eq!(record.expiration_length, 90);
+		assert_eq!(Balances::free_balance(public), 100);
+	});
+}
+
+#[test]
+fn legacy_identity_lists_should_migrate() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let identity_hash = register_and_attest_github(2);
+		let verified_hash = build_identity_hash(b"twitter", b"drewstone");
+		<IdentitySet<Test>>::remove(identity_hash);
+		<Expirations<Test>>::remove(10001);
+		<BondOf<Test>>::remove(identity_hash);
+		<Identities<Test>>::put(vec![identity_hash, verified_hash]);
+		<IdentitiesPending<Test>>::put(vec![(identity_hash, 10001), (verified_hash, 0)]);
+		<IdentitiesMigrated>::put(false);
+
+		<Identity as OnInitialize<u64>>::on_initialize(2);
+		assert!(Identity::identities_migrated());
+		let identities = Identity::identities();
+		assert_eq!(identities.len(), 2);
+		assert!(identities.contains(&identity_hash) && identities.contains(&verified_hash));
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
+		assert_eq!(Identity::expirations(0), vec![]);
+		assert_eq!(Identity::bond_of(identity_hash), BOND);
+		assert!(!<Identities<Test>>::exists());
+		assert!(!<IdentitiesPending<Test>>::exists());
+
+		// Migrating again does nothing
+		<Identities<Test>>::put(vec![H256::zero()]);
+		<Identity as OnInitialize<u64>>::on_initialize(3);
+		assert_eq!(Identity::identities().len(), 2);
+	});
+}
+
+#[test]
+fn migration_should_seed_identity_configuration() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		<IdentityTypes>::remove(b"github".to_vec());
+		<IdentityTypes>::remove(b"ethereum".to_vec());
+		<IdentityTypes>::insert(b"twitter".to_vec(), handle_rules(20));
+		<DisputeWindow<Test>>::put(0);
+		<IdentitiesMigrated>::put(false);
+
+		<Identity as OnInitialize<u64>>::on_initialize(1);
+		for (identity_type, rules) in default_identity_types() {
+			assert!(Identity::identity_types(&identity_type).is_some());
+			if identity_type == b"github".to_vec() {
+				assert_eq!(Identity::identity_types(&identity_type), Some(rules));
+			}
+		}
+		// Types configured before the migration are kept
+		assert_eq!(Identity::identity_types(b"twitter".to_vec()), Some(handle_rules(20)));
+		assert_eq!(Identity::dispute_window(), Identity::expiration_length());
+		assert_ok!(register_identity(2, b"github", b"drewstone"));
+		assert_err!(register_identity(2, b"ethereum", b"deadbeef"), "Identity does not match identity type");
+	});
+}
+
+#[test]
+fn migration_should_seed_deposits() {
+	new_test_ext().execute_with(|| {
+		<RegistrationBond<Test>>::put(100_000);
+		<MetadataByteDeposit<Test>>::put(0);
+		<VerificationFee<Test>>::put(0);
+		<VerifierSlashShare>::put(Perbill::zero());
+		<AppealDeposit<Test>>::put(0);
+		<AppealReviewPeriod<Test>>::put(0);
+		<IdentitiesMigrated>::put(false);
+
+		<Identity as OnInitialize<u64>>::on_initialize(1);
+		assert_eq!(Identity::metadata_byte_deposit(), 10);
+		assert_eq!(Identity::verification_fee(), 10_000);
+		assert_eq!(Identity::verifier_slash_share(), Perbill::from_percent(50));
+		assert_eq!(Identity::appeal_deposit(), 100_000);
+		assert_eq!(Identity::appeal_review_period(), DISPUTE_WINDOW);
+	});
+}
+
+#[test]
+fn verifier_rewards_should_be_settable() {
+	new_test_ext().execute_with(|| {
+		assert!(Identity::set_verification_fee(Origin::signed(1), 4).is_err());
+		assert!(Identity::set_verifier_slash_share(Origin::signed(1), Perbill::from_percent(20)).is_err());
+		assert_ok!(Identity::set_verification_fee(Origin::ROOT, 4));
+		assert_ok!(Identity::set_verifier_slash_share(Origin::ROOT, Perbill::from_percent(20)));
+		assert_eq!(Identity::verification_fee(), 4);
+		assert_eq!(Identity::verifier_slash_share(), Perbill::from_percent(20));
+
+		System::set_block_number(1);
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_eq!(Balances::free_balance(public), 100 - 4);
+	});
+}
+
+#[test]
+fn set_metadata_byte_deposit_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert!(Identity::set_metadata_byte_deposit(Origin::signed(1), 2).is_err());
+		assert_ok!(Identity::set_metadata_byte_deposit(Origin::ROOT, 2));
+		assert_eq!(Identity::metadata_byte_deposit(), 2);
+
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_ok!(add_metadata_to_account(public, identity_hash, b"", b"drew", b""));
+		assert_eq!(Identity::metadata_deposit(identity_hash), 8);
+	});
+}
+
+#[test]
+fn metadata_limits_should_default_when_unset() {
+	new_test_ext().execute_with(|| {
+		<MaxAvatarLength>::kill();
+		<MaxDisplayNameLength>::kill();
+		<MaxTaglineLength>::kill();
+		assert_eq!(Identity::max_avatar_length(), 256);
+		assert_eq!(Identity::max_display_name_length(), 64);
+		assert_eq!(Identity::max_tagline_length(), 256);
+	});
+}
+
+#[test]
+fn moved_expiration_should_not_expire_identity() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 2_u64;
+		let identity_hash = build_identity_hash(b"github", b"drewstone");
+		assert_ok!(register_identity(public, b"github", b"drewstone"));
+		System::set_block_number(5);
+		assert_ok!(attest_to_identity(public, identity_hash, b"www.proof.com/attest_of_extra_proof"));
+		assert_eq!(Identity::expirations(10001), vec![]);
+		assert_eq!(Identity::expirations(10005), vec![identity_hash]);
+
+		finalize_at(10002);
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Attested);
+		finalize_at(10006);
+		assert_eq!(Identity::identity_of(identity_hash), None);
+		assert_eq!(Identity::identities(), vec![]);
+	});
+}
+
+fn add_github_metadata(public: u64) -> H256 {
+	let identity_hash = build_identity_hash(b"github", b"drewstone");
+	assert_ok!(register_identity(public, b"github", b"drewstone"));
+	assert_ok!(add_metadata_to_account(public, identity_hash, b"avatar", b"drewstone", b"hello world!"));
+	identity_hash
+}
+
+#[test]
+fn add_metadata_should_reserve_byte_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 1_u64;
+		let identity_hash = add_github_metadata(public);
+		assert_eq!(Identity::metadata_deposit(identity_hash), 27);
+		assert_eq!(Balances::reserved_balance(public), BOND + 27);
+
+		// Shorter metadata returns the difference
+		assert_ok!(add_metadata_to_account(public, identity_hash, b"", b"drew", b""));
+		assert_eq!(Identity::metadata_deposit(identity_hash), 4);
+		assert_eq!(Balances::reserved_balance(public), BOND + 4);
+
+		assert_err!(
+			add_metadata_to_account(2, identity_hash, b"", b"drew", b""),
+			"Stored identity does not match sender"
+		);
+	});
+}
+
+#[test]
+fn add_metadata_should_enforce_limits() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 1_u64;
+		let identity_hash = build_identity_hash(b"github", b"drewstone");
+		assert_ok!(register_identity(public, b"github", b"drewstone"));
+		assert_err!(add_metadata_to_account(public, identity_hash, &[0u8; 65], b"", b""), "Avatar too long");
+		assert_err!(add_metadata_to_account(public, identity_hash, b"", &[0u8; 17], b""), "Display name too long");
+		assert_err!(add_metadata_to_account(public, identity_hash, b"", b"", &[0u8; 33]), "Tagline too long");
+
+		// Not enough free balance for the deposit
+		assert_ok!(Balances::transfer(Origin::signed(public), 2, 100 - BOND - 10));
+		assert_err!(
+			add_metadata_to_account(public, identity_hash, &[0u8; 64], b"", b""),
+			"Not enough currency for metadata deposit"
+		);
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().metadata, None);
+	});
+}
+
+#[test]
+fn clear_metadata_should_refund_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 1_u64;
+		let identity_hash = add_github_metadata(public);
+		assert_err!(Identity::clear_metadata(Origin::signed(2), identity_hash), "Stored identity does not match sender");
+		assert_ok!(Identity::clear_metadata(Origin::signed(public), identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().metadata, None);
+		assert_eq!(Identity::metadata_deposit(identity_hash), 0);
+		assert_eq!(Balances::reserved_balance(public), BOND);
+		assert_err!(Identity::clear_metadata(Origin::signed(public), identity_hash), "Identity has no metadata");
+	});
+}
+
+#[test]
+fn force_clear_metadata_should_slash_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 1_u64;
+		let identity_hash = add_github_metadata(public);
+		assert!(Identity::force_clear_metadata(Origin::signed(2), identity_hash).is_err());
+		assert_ok!(Identity::force_clear_metadata(Origin::ROOT, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().metadata, None);
+		assert_eq!(Balances::reserved_balance(public), BOND);
+		assert_eq!(Balances::free_balance(public), 100 - BOND - 27);
+	});
+}
+
+#[test]
+fn expired_identity_should_refund_metadata_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 1_u64;
+		let identity_hash = add_github_metadata(public);
+		finalize_at(10002);
+		assert_eq!(Identity::identity_of(identity_hash), None);
+		assert_eq!(Balances::reserved_balance(public), 0);
+		assert_eq!(Identity::metadata_deposit(identity_hash), 0);
+		assert_eq!(Identity::bond_of(identity_hash), 0);
+	});
+}
+
+#[test]
+fn revoke_should_refund_bond_and_free_type() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_ok!(add_metadata_to_account(public, identity_hash, b"", b"drew", b""));
+		assert_eq!(Balances::reserved_balance(public), BOND + 4);
+
+		assert_ok!(revoke(public, identity_hash));
+		assert_eq!(Identity::identity_of(identity_hash), None);
+		assert_eq!(Identity::identities(), vec![]);
+		assert_eq!(Identity::expirations(10001), vec![]);
+		assert_eq!(Identity::used_types(public), Vec::<Vec<u8>>::new());
+		assert_eq!(Balances::free_balance(public), 100);
+
+		// The type can be registered again
+		assert_ok!(register_identity(public, b"github", b"drewstone"));
+	});
+}
+
+#[test]
+fn revoke_verified_should_not_refund_bond_twice() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_ok!(verify_identity(1, identity_hash));
+		// Another pending identity keeps its bond reserved
+		assert_ok!(register_identity(public, b"twitter", b"drewstone"));
+
+		assert_ok!(revoke(public, identity_hash));
+		assert_eq!(Balances::reserved_balance(public), BOND);
+		assert_eq!(Identity::used_types(public), vec![b"twitter".to_vec()]);
+	});
+}
+
+#[test]
+fn expired_pending_identity_should_refund_bond_and_free_type() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 2_u64;
+		assert_ok!(register_identity(public, b"twitter", b"drewstone"));
+		let registered_hash = build_identity_hash(b"twitter", b"drewstone");
+		let attested_hash = register_and_attest_github(public);
+		assert_eq!(Balances::reserved_balance(public), 2 * BOND);
+
+		finalize_at(10002);
+		assert_eq!(Identity::identity_of(registered_hash), None);
+		assert_eq!(Identity::identity_of(attested_hash), None);
+		assert_eq!(Identity::used_types(public), Vec::<Vec<u8>>::new());
+		assert_eq!(Balances::reserved_balance(public), 0);
+		assert_eq!(Balances::free_balance(public), 100);
+	});
+}
+
+fn transfer_identity(who: u64, identity_hash: H256, new_account: u64) -> Result {
+	Identity::transfer_identity(Origin::signed(who), identity_hash, new_account)
+}
+
+fn accept_identity(who: u64, identity_hash: H256) -> Result {
+	Identity::accept_identity(Origin::signed(who), identity_hash)
+}
+
+#[test]
+fn transfer_verified_identity_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (old, new) = (2_u64, 3_u64);
+		let identity_hash = register_and_attest_github(old);
+		assert_ok!(verify_identity(1, identity_hash));
+		assert_ok!(add_metadata_to_account(old, identity_hash, b"", b"drew", b""));
+
+		assert_err!(transfer_identity(new, identity_hash, new), "Stored identity does not match sender");
+		assert_err!(transfer_identity(old, identity_hash, old), "Identity already belongs to account");
+		assert_ok!(transfer_identity(old, identity_hash, new));
+		assert_eq!(Identity::pending_transfers(identity_hash), Some(new));
+		assert_err!(accept_identity(4, identity_hash), "No pending transfer to sender");
+		assert_ok!(accept_identity(new, identity_hash));
+
+		let record = Identity::identity_of(identity_hash).unwrap();
+		assert_eq!(record.account, new);
+		assert_eq!(record.stage, IdentityStage::Verified);
+		assert!(record.metadata.is_some());
+		assert_eq!(Identity::pending_transfers(identity_hash), None);
+		assert_eq!(Identity::used_types(old), Vec::<Vec<u8>>::new());
+		assert_eq!(Identity::used_types(new), vec![b"github".to_vec()]);
+		assert_eq!(Balances::reserved_balance(old), 0);
+		assert_eq!(Balances::free_balance(old), 100);
+		assert_eq!(Balances::reserved_balance(new), 4);
+
+		// Only the new account controls the identity
+		assert_err!(revoke(old, identity_hash), "Stored identity does not match sender");
+		assert_ok!(revoke(new, identity_hash));
+		assert_eq!(Balances::free_balance(new), 100);
+	});
+}
+
+#[test]
+fn transfer_ethereum_identity_should_require_new_signature() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (old, new) = (2_u64, 3_u64);
+		let secret = ethereum_secret(b"Alice");
+		let address = ethereum_address(&secret);
+		let identity_hash = build_identity_hash(b"ethereum", &address);
+		assert_ok!(register_and_attest(old, b"ethereum", &address, &ethereum_signature(&secret, old)));
+		assert_ok!(transfer_identity(old, identity_hash, new));
+		assert_ok!(accept_identity(new, identity_hash));
+
+		let record = Identity::identity_of(identity_hash).unwrap();
+		assert_eq!(record.account, new);
+		assert_eq!(record.stage, IdentityStage::Registered);
+		assert_eq!(record.proof, None);
+		assert_eq!(Identity::expirations(10001), vec![identity_hash]);
+		assert!(!<Identity as IdentityProvider<u64>>::is_verified(&new, b"ethereum"));
+
+		// The signature for the previous account does not link the new one
+		assert_err!(
+			attest_to_identity(new, identity_hash, &ethereum_signature(&secret, old)),
+			"Ethereum signature does not match identity"
+		);
+		assert_ok!(attest_to_identity(new, identity_hash, &ethereum_signature(&secret, new)));
+		assert!(<Identity as IdentityProvider<u64>>::is_verified(&new, b"ethereum"));
+		assert_eq!(Balances::reserved_balance(new), 0);
+	});
+}
+
+#[test]
+fn transfer_registered_identity_should_move_bond() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (old, new) = (2_u64, 3_u64);
+		let identity_hash = build_identity_hash(b"github", b"drewstone");
+		assert_ok!(register_identity(old, b"github", b"drewstone"));
+		assert_ok!(transfer_identity(old, identity_hash, new));
+		assert_ok!(accept_identity(new, identity_hash));
+		assert_eq!(Balances::reserved_balance(old), 0);
+		assert_eq!(Balances::reserved_balance(new), BOND);
+		assert_eq!(Identity::identity_of(identity_hash).unwrap().stage, IdentityStage::Registered);
+	});
+}
+
+#[test]
+fn transfer_identity_should_respect_stage_and_types() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (old, new) = (2_u64, 3_u64);
+		let identity_hash = build_identity_hash(b"github", b"drewstone");
+		assert_ok!(register_identity(old, b"github", b"drewstone"));
+		assert_ok!(register_identity(new, b"github", b"drstone"));
+		assert_ok!(transfer_identity(old, identity_hash, new));
+		assert_err!(accept_identity(new, identity_hash), "Identity type already used");
+
+		// Proofs are bound to the attesting account
+		assert_ok!(attest_to_identity(old, identity_hash, b"www.proof.com/attest_of_extra_proof"));
+		assert_err!(transfer_identity(old, identity_hash, 4), "Identity awaiting verification");
+		assert_err!(accept_identity(new, identity_hash), "Identity awaiting verification");
+
+		assert_ok!(Identity::cancel_identity_transfer(Origin::signed(old), identity_hash));
+		assert_eq!(Identity::pending_transfers(identity_hash), None);
+		assert_err!(
+			Identity::cancel_identity_transfer(Origin::signed(old), identity_hash),
+			"No pending transfer"
+		);
+	});
+}
+
+#[test]
+fn identity_provider_should_report_verified_identities() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = 2_u64;
+		let identity_hash = register_and_attest_github(public);
+		assert_ok!(register_identity(public, b"twitter", b"drewstone"));
+		assert!(!<Identity as IdentityProvider<u64>>::is_verified(&public, b"github"));
+
+		assert_ok!(verify_identity(1, identity_hash));
+		assert!(<Identity as IdentityProvider<u64>>::is_verified(&public, b"github"));
+		assert!(!<Identity as IdentityProvider<u64>>::is_verified(&public, b"twitter"));
+		assert!(!<Identity as IdentityProvider<u64>>::is_verified(&3, b"github"));
+		assert_eq!(<Identity as IdentityProvider<u64>>::verified_types(&public), vec![b"github".to_vec()]);
+
+		// Transferred identities are verified for the new account
+		assert_ok!(transfer_identity(public, identity_hash, 3));
+		assert_ok!(accept_identity(3, identity_hash));
+		assert!(!<Identity as IdentityProvider<u64>>::is_verified(&public, b"github"));
+		assert!(<Identity as IdentityProvider<u64>>::is_verified(&3, b"github"));
+
+		assert_ok!(revoke(3, identity_hash));
+		assert_eq!(<Identity as IdentityProvider<u64>>::verified_types(&3), Vec::<Vec<u8>>::new());
+	});
+}
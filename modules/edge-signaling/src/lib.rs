@@ -24,18 +24,52 @@ mod tests;
 use support::traits::{Currency, ReservableCurrency};
 use rstd::prelude::*;
 
-use system::ensure_signed;
+use system::{ensure_signed, ensure_root};
 use support::dispatch::Result;
 use codec::{Decode, Encode};
 
 use sr_primitives::RuntimeDebug;
-use sr_primitives::traits::{Hash};
+use sr_primitives::traits::{Hash, Saturating, Zero};
 use support::{decl_event, decl_module, decl_storage, ensure, StorageMap};
+use support::storage::unhashed;
+use identity::IdentityProvider;
 
 pub use voting::{VoteType, VoteOutcome, VoteStage, TallyType};
 
+/// Maximum length in bytes of an IPFS CID attached to a proposal.
+pub const MAX_IPFS_CID_LENGTH: usize = 128;
+
+/// Ratio of the proposal creation bond to the per-byte preimage deposit
+/// set on chains upgraded from before preimages were introduced.
+pub const PREIMAGE_BYTE_DEPOSIT_RATIO: u32 = 10_000_000;
+
+/// Ratio of the proposal creation bond to the seconding deposit set on
+/// chains upgraded from before seconding was introduced.
+pub const SECONDING_DEPOSIT_RATIO: u32 = 100;
+
+/// Number of seconds that advance a proposal on chains upgraded from
+/// before seconding was introduced.
+pub const DEFAULT_REQUIRED_SECONDS: u32 = 5;
+
 #[derive(Encode, Decode, Clone, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug)]
-pub struct ProposalRecord<AccountId, Moment> {
+pub struct ProposalRecord<AccountId, Moment, Hash> {
+	pub index: u32,
+	pub author: AccountId,
+	pub stage: VoteStage,
+	pub transition_time: Moment,
+	pub title: Vec<u8>,
+	pub contents_hash: Hash,
+	pub ipfs_cid: Option<Vec<u8>>,
+	pub vote_id: u64,
+	/// Contents hashes of every version of the proposal, oldest first
+	pub versions: Vec<Hash>,
+	/// Length of the voting stage chosen by the author
+	pub voting_length: Moment,
+}
+
+/// Layout of `ProposalRecord` before the contents were noted as preimages.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct LegacyProposalRecord<AccountId, Moment> {
 	pub index: u32,
 	pub author: AccountId,
 	pub stage: VoteStage,
@@ -45,38 +79,132 @@ pub struct ProposalRecord<AccountId, Moment> {
 	pub vote_id: u64,
 }
 
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug)]
+pub struct ArchivedProposal<AccountId, BlockNumber, Hash, Balance> {
+	/// Hash of the proposal while it was live
+	pub proposal_hash: Hash,
+	/// Author of the proposal
+	pub author: AccountId,
+	/// Identifier of the vote in the voting module
+	pub vote_id: u64,
+	/// Contents hash of the final version of the proposal
+	pub contents_hash: Hash,
+	/// How the votes were weighted
+	pub tally_type: TallyType,
+	/// Weight of the votes ranking each outcome first: the number of voters
+	/// for `OnePerson`, or their total balance at completion for `OneCoin`
+	pub first_choice_tally: Vec<(VoteOutcome, Balance)>,
+	/// Block at which voting completed
+	pub completed_at: BlockNumber,
+}
+
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug)]
+pub struct PreimageRecord<AccountId, Balance> {
+	/// The raw proposal contents
+	pub contents: Vec<u8>,
+	/// Account that noted the preimage and paid the deposit
+	pub provider: AccountId,
+	/// Deposit reserved from the provider
+	pub deposit: Balance,
+	/// Number of proposals currently referencing this preimage
+	pub uses: u32,
+}
+
 pub trait Trait: voting::Trait + balances::Trait {
 	/// The overarching event type
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 	/// The account balance.
 	type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
+	/// Source of the verified identities of proposal authors.
+	type IdentityProvider: IdentityProvider<Self::AccountId>;
 }
 
 pub type ProposalTitle = Vec<u8>;
 pub type ProposalContents = Vec<u8>;
+pub type IpfsCid = Vec<u8>;
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
 
+		/// Migrate proposals stored before the contents were noted as preimages.
+		fn on_initialize(_n: T::BlockNumber) {
+			Self::migrate_proposals();
+		}
+
+		/// Notes the contents of a proposal on-chain, indexed by their hash.
+		///
+		/// A deposit proportional to the byte length of the contents is
+		/// reserved from the sender and returned once no proposal refers to
+		/// the preimage anymore.
+		pub fn note_preimage(origin, contents: ProposalContents) -> Result {
+			let _sender = ensure_signed(origin)?;
+			ensure!(!contents.is_empty(), "Proposal must not be empty");
+			let hash = T::Hashing::hash(&contents[..]);
+			ensure!(!<Preimages<T>>::exists(hash), "Preimage already noted");
+
+			let deposit = <BalanceOf<T>>::from(contents.len() as u32)
+				.saturating_mul(Self::preimage_byte_deposit());
+			T::Currency::reserve(&_sender, deposit).map_err(|_| "Not enough currency for preimage deposit")?;
+			<Preimages<T>>::insert(hash, PreimageRecord {
+				contents: contents,
+				provider: _sender.clone(),
+				deposit: deposit,
+				uses: 0,
+			});
+			Self::deposit_event(RawEvent::PreimageNoted(hash, _sender, deposit));
+			Ok(())
+		}
+
+		/// Removes a noted preimage that is not referenced by any proposal and
+		/// returns the deposit. Can only be performed by the provider.
+		pub fn clear_preimage(origin, contents_hash: T::Hash) -> Result {
+			let _sender = ensure_signed(origin)?;
+			let preimage = <Preimages<T>>::get(&contents_hash).ok_or("Preimage does not exist")?;
+			ensure!(preimage.provider == _sender, "Preimage must be cleared by provider");
+			ensure!(preimage.uses == 0, "Preimage is in use");
+			T::Currency::unreserve(&preimage.provider, preimage.deposit);
+			<Preimages<T>>::remove(contents_hash);
+			Self::deposit_event(RawEvent::PreimageCleared(contents_hash));
+			Ok(())
+		}
+
 		/// Creates a new signaling proposal.
+		///
+		/// The contents must have been noted beforehand with `note_preimage`;
+		/// only their hash and an optional IPFS CID are kept in the record.
+		/// The author may pick the length of the voting stage within the
+		/// configured bounds, otherwise the default voting length is used.
 		pub fn create_proposal(
 			origin,
 			title: ProposalTitle,
-			contents: ProposalContents,
+			contents_hash: T::Hash,
+			ipfs_cid: Option<IpfsCid>,
 			outcomes: Vec<VoteOutcome>,
 			vote_type: voting::VoteType,
-			tally_type: voting::TallyType
+			tally_type: voting::TallyType,
+			voting_length: Option<T::BlockNumber>
 		) -> Result {
 			let _sender = ensure_signed(origin)?;
+			ensure!(
+				!Self::author_identity_required() || !T::IdentityProvider::verified_types(&_sender).is_empty(),
+				"Proposal author must have a verified identity"
+			);
+			let voting_length = voting_length.unwrap_or_else(Self::voting_length);
+			ensure!(voting_length >= Self::min_voting_length(), "Voting length too short");
+			ensure!(voting_length <= Self::max_voting_length(), "Voting length too long");
 			ensure!(!title.is_empty(), "Proposal must have title");
-			ensure!(!contents.is_empty(), "Proposal must not be empty");
+			ensure!(title.len() <= Self::max_title_length() as usize, "Proposal title too long");
+			if let Some(cid) = &ipfs_cid {
+				ensure!(!cid.is_empty() && cid.len() <= MAX_IPFS_CID_LENGTH, "Invalid IPFS CID");
+			}
+			ensure!(<Preimages<T>>::exists(contents_hash), "Proposal preimage not noted");
 
-			// construct hash(origin + proposal) and check existence
+			// construct hash(origin + contents hash) and check existence
 			let mut buf = Vec::new();
 			buf.extend_from_slice(&_sender.encode());
-			buf.extend_from_slice(&contents.as_ref());
+			buf.extend_from_slice(&contents_hash.encode());
 			let hash = T::Hashing::hash(&buf[..]);
 			ensure!(<ProposalOf<T>>::get(hash) == None, "Proposal already exists");
 
@@ -91,8 +219,11 @@ decl_module! {
 				outcomes,
 			)?;
 
+			<Preimages<T>>::mutate(contents_hash, |preimage| {
+				if let Some(p) = preimage { p.uses += 1; }
+			});
 			let index = <ProposalCount>::get();
-			let transition_time = <system::Module<T>>::block_number() + Self::voting_length();
+			let transition_time = <system::Module<T>>::block_number() + Self::prevoting_length();
 			<ProposalCount>::mutate(|i| *i += 1);
 			<ProposalOf<T>>::insert(hash, ProposalRecord {
 				index: index,
@@ -100,14 +231,54 @@ decl_module! {
 				stage: VoteStage::PreVoting,
 				transition_time: transition_time,
 				title: title,
-				contents: contents,
+				contents_hash: contents_hash,
+				ipfs_cid: ipfs_cid,
 				vote_id: vote_id,
+				versions: vec![contents_hash],
+				voting_length: voting_length,
 			});
 			<InactiveProposals<T>>::mutate(|proposals| proposals.push((hash, transition_time)));
 			Self::deposit_event(RawEvent::NewProposal(_sender, hash));
 			Ok(())
 		}
 
+		/// Amends the contents of a signaling proposal.
+		///
+		/// Can only be performed by the original author while the proposal is
+		/// still in the "prevoting" stage and has not been seconded, so that
+		/// seconds always back the current version. The new contents must have
+		/// been noted with `note_preimage`; previous versions are kept in the record.
+		pub fn amend_proposal(
+			origin,
+			proposal_hash: T::Hash,
+			contents_hash: T::Hash,
+			ipfs_cid: Option<IpfsCid>
+		) -> Result {
+			let _sender = ensure_signed(origin)?;
+			let mut record = <ProposalOf<T>>::get(&proposal_hash).ok_or("Proposal does not exist")?;
+
+			// only permit original author to amend, and only before voting
+			ensure!(record.author == _sender, "Proposal must be amended by author");
+			ensure!(record.stage == VoteStage::PreVoting, "Proposal not in pre-voting stage");
+			ensure!(<SecondsOf<T>>::get(&proposal_hash).is_empty(), "Cannot amend a seconded proposal");
+			if let Some(cid) = &ipfs_cid {
+				ensure!(!cid.is_empty() && cid.len() <= MAX_IPFS_CID_LENGTH, "Invalid IPFS CID");
+			}
+			ensure!(<Preimages<T>>::exists(contents_hash), "Proposal preimage not noted");
+			ensure!(!record.versions.contains(&contents_hash), "Proposal version already exists");
+
+			<Preimages<T>>::mutate(contents_hash, |preimage| {
+				if let Some(p) = preimage { p.uses += 1; }
+			});
+			record.contents_hash = contents_hash;
+			record.ipfs_cid = ipfs_cid;
+			record.versions.push(contents_hash);
+			let version = record.versions.len() as u32;
+			<ProposalOf<T>>::insert(proposal_hash, record);
+			Self::deposit_event(RawEvent::ProposalAmended(proposal_hash, contents_hash, version));
+			Ok(())
+		}
+
 		/// Advance a signaling proposal into the "voting" or "commit" stage.
 		/// Can only be performed by the original author of the proposal.
 		pub fn advance_proposal(origin, proposal_hash: T::Hash) -> Result {
@@ -119,32 +290,93 @@ decl_module! {
 			ensure!(record.stage == VoteStage::PreVoting
 				|| record.stage == VoteStage::Commit, "Proposal not in pre-voting or commit stage");
 
-			// prevoting -> voting or commit
-			<voting::Module<T>>::advance_stage(record.vote_id)?;
-			if let Some(vote_record) = <voting::Module<T>>::get_vote_record(record.vote_id) {
-				let transition_time = <system::Module<T>>::block_number() + Self::voting_length();
-				let vote_id = record.vote_id;
-				<ProposalOf<T>>::insert(proposal_hash, ProposalRecord {
-					stage: vote_record.data.stage,
-					transition_time: transition_time.clone(),
-					..record
-				});
-				<InactiveProposals<T>>::mutate(|proposals| proposals.retain(|x| x.0 != proposal_hash));
-				<ActiveProposals<T>>::mutate(|proposals| proposals.push((proposal_hash, transition_time.clone())));
-
-				// emit event for voting if at this stage
-				if vote_record.data.stage == VoteStage::Voting {
-					Self::deposit_event(RawEvent::VotingStarted(proposal_hash, vote_id, transition_time));
-				}
+			Self::do_advance_proposal(proposal_hash, record)
+		}
 
-				// emit event for committing if at this stage
-				if vote_record.data.stage == VoteStage::Commit {
-					Self::deposit_event(RawEvent::CommitStarted(proposal_hash, vote_id, transition_time));
-				}
-				Ok(())
-			} else {
-				Err("Vote record does not exist")
+		/// Seconds a signaling proposal in the "prevoting" stage, reserving
+		/// `amount`, at least the seconding deposit, until the proposal
+		/// completes or expires.
+		///
+		/// Once the configured number of seconds or amount of reserved backing
+		/// is reached, the proposal is advanced automatically.
+		pub fn second(origin, proposal_hash: T::Hash, amount: BalanceOf<T>) -> Result {
+			let _sender = ensure_signed(origin)?;
+			let record = <ProposalOf<T>>::get(&proposal_hash).ok_or("Proposal does not exist")?;
+			ensure!(record.stage == VoteStage::PreVoting, "Proposal not in pre-voting stage");
+			ensure!(record.author != _sender, "Author cannot second own proposal");
+			let mut seconds = <SecondsOf<T>>::get(&proposal_hash);
+			ensure!(!seconds.iter().any(|(who, _)| who == &_sender), "Proposal already seconded");
+			ensure!(amount >= Self::seconding_deposit(), "Seconding amount below deposit");
+
+			T::Currency::reserve(&_sender, amount).map_err(|_| "Not enough currency for seconding deposit")?;
+			seconds.push((_sender.clone(), amount));
+			<SecondsOf<T>>::insert(proposal_hash, &seconds);
+			Self::deposit_event(RawEvent::Seconded(proposal_hash, _sender));
+
+			// advance the proposal if it has gathered enough support
+			let required_seconds = Self::required_seconds();
+			let required_backing = Self::required_backing();
+			let backing = seconds.iter()
+				.fold(Zero::zero(), |acc: BalanceOf<T>, (_, deposit)| acc.saturating_add(*deposit));
+			if (required_seconds > 0 && seconds.len() as u32 >= required_seconds)
+				|| (!required_backing.is_zero() && backing >= required_backing) {
+				Self::do_advance_proposal(proposal_hash, record)?;
 			}
+			Ok(())
+		}
+
+		/// Sets the default lengths of the "prevoting", "commit" and "voting" stages.
+		pub fn set_stage_lengths(
+			origin,
+			prevoting_length: T::BlockNumber,
+			commit_length: T::BlockNumber,
+			voting_length: T::BlockNumber
+		) -> Result {
+			ensure_root(origin)?;
+			ensure!(!prevoting_length.is_zero(), "Prevoting length must be positive");
+			ensure!(!commit_length.is_zero(), "Commit length must be positive");
+			ensure!(voting_length >= Self::min_voting_length()
+				&& voting_length <= Self::max_voting_length(), "Voting length out of bounds");
+			<PrevotingLength<T>>::put(prevoting_length);
+			<CommitLength<T>>::put(commit_length);
+			<VotingLength<T>>::put(voting_length);
+			Self::deposit_event(RawEvent::StageLengthsChanged(prevoting_length, commit_length, voting_length));
+			Ok(())
+		}
+
+		/// Sets the bounds within which authors may pick a voting length.
+		pub fn set_voting_length_bounds(origin, min: T::BlockNumber, max: T::BlockNumber) -> Result {
+			ensure_root(origin)?;
+			ensure!(min <= max, "Invalid voting length bounds");
+			ensure!(Self::voting_length() >= min
+				&& Self::voting_length() <= max, "Default voting length out of bounds");
+			<MinVotingLength<T>>::put(min);
+			<MaxVotingLength<T>>::put(max);
+			Ok(())
+		}
+
+		/// Sets the seconding deposit and the number of seconds or amount of
+		/// reserved backing that advance a proposal, 0 to disable either.
+		pub fn set_seconding_parameters(
+			origin,
+			deposit: BalanceOf<T>,
+			required_seconds: u32,
+			required_backing: BalanceOf<T>
+		) -> Result {
+			ensure_root(origin)?;
+			<SecondingDeposit<T>>::put(deposit);
+			<RequiredSeconds>::put(required_seconds);
+			<RequiredBacking<T>>::put(required_backing);
+			Self::deposit_event(RawEvent::SecondingParametersChanged(deposit, required_seconds, required_backing));
+			Ok(())
+		}
+
+		/// Sets whether proposal authors must have a verified identity.
+		pub fn set_author_identity_required(origin, required: bool) -> Result {
+			ensure_root(origin)?;
+			<AuthorIdentityRequired>::put(required);
+			Self::deposit_event(RawEvent::AuthorIdentityRequiredChanged(required));
+			Ok(())
 		}
 
 		/// Check all active proposals to see if they're completed. If so, update
@@ -168,12 +400,21 @@ decl_module! {
 						let vote_id = record.vote_id;
 						let _ = <voting::Module<T>>::advance_stage(vote_id);
 						if let Some(vote_record) = <voting::Module<T>>::get_vote_record(record.vote_id) {
-							// get next transition time
-							let transition_time = <system::Module<T>>::block_number() + Self::voting_length();
+							// get next transition time, completed proposals are kept
+							// for the default voting length before deletion
+							let now = <system::Module<T>>::block_number();
+							let transition_time = match vote_record.data.stage {
+								VoteStage::Voting => now + record.voting_length,
+								_ => now + Self::voting_length(),
+							};
 							// switch on either completed or voting stage from voting or committed stage
 							if vote_record.data.stage == VoteStage::Completed {
 								// unreserve the proposal creation bond amount
 								T::Currency::unreserve(&record.author, Self::proposal_creation_bond());
+								// return the deposits of all seconders
+								Self::return_seconding_deposits(&finished_hash);
+								// keep a summary of the proposal once its record is deleted
+								Self::archive_proposal(finished_hash, &record, &vote_record);
 								// add these completed proposals, to the pending "deletion" collection
 								pending.push((finished_hash, transition_time.clone()));
 								Self::deposit_event(RawEvent::VotingCompleted(finished_hash, vote_id));
@@ -196,7 +437,7 @@ decl_module! {
 
 			// delete all artifacts of "completed", completed proposals
 			completed.iter().for_each(|(finished_hash, _)| {
-				<ProposalOf<T>>::remove(finished_hash);
+				Self::remove_proposal(finished_hash);
 			});
 			// we want to delete the doubly inactive, inactive proposals which never
 			// proceeded into a commit or voting stage, always remaining in prevoting,
@@ -205,7 +446,8 @@ decl_module! {
 				if let Some(record) = <ProposalOf<T>>::get(hash) {
 					T::Currency::unreserve(&record.author, Self::proposal_creation_bond());
 				}
-				<ProposalOf<T>>::remove(hash);
+				Self::return_seconding_deposits(&hash);
+				Self::remove_proposal(&hash);
 			});
 
 			// put back all active proposals
@@ -218,10 +460,174 @@ decl_module! {
 	}
 }
 
+impl<T: Trait> Module<T> {
+	/// Rewrites the proposals stored in the legacy layout, noting their
+	/// contents as preimages, and sets the configuration added since
+	fn migrate_proposals() {
+		if Self::proposals_migrated() {
+			return;
+		}
+		// stages used to last the voting length, and authors could not pick it
+		let voting_length = Self::voting_length();
+		if Self::prevoting_length().is_zero() {
+			<PrevotingLength<T>>::put(voting_length);
+		}
+		if Self::commit_length().is_zero() {
+			<CommitLength<T>>::put(voting_length);
+		}
+		if Self::max_voting_length().is_zero() {
+			<MinVotingLength<T>>::put(voting_length);
+			<MaxVotingLength<T>>::put(voting_length);
+		}
+		if Self::preimage_byte_deposit().is_zero() {
+			let ratio = <BalanceOf<T>>::from(PREIMAGE_BYTE_DEPOSIT_RATIO);
+			<PreimageByteDeposit<T>>::put(Self::proposal_creation_bond() / ratio);
+		}
+		if Self::seconding_deposit().is_zero() {
+			let ratio = <BalanceOf<T>>::from(SECONDING_DEPOSIT_RATIO);
+			<SecondingDeposit<T>>::put(Self::proposal_creation_bond() / ratio);
+		}
+		if Self::required_seconds() == 0 && Self::required_backing().is_zero() {
+			<RequiredSeconds>::put(DEFAULT_REQUIRED_SECONDS);
+		}
+
+		let hashes = Self::inactive_proposals().into_iter()
+			.chain(Self::active_proposals())
+			.chain(Self::completed_proposals())
+			.map(|(hash, _)| hash);
+		for hash in hashes {
+			let key = <ProposalOf<T>>::hashed_key_for(&hash);
+			let legacy = match unhashed::get::<LegacyProposalRecord<T::AccountId, T::BlockNumber>>(&key) {
+				Some(legacy) => legacy,
+				None => continue,
+			};
+			// the contents were paid for by the creation bond, so no deposit is held
+			let contents_hash = T::Hashing::hash(&legacy.contents[..]);
+			<Preimages<T>>::mutate(contents_hash, |preimage| match preimage {
+				Some(p) => p.uses += 1,
+				None => *preimage = Some(PreimageRecord {
+					contents: legacy.contents.clone(),
+					provider: legacy.author.clone(),
+					deposit: Zero::zero(),
+					uses: 1,
+				}),
+			});
+			<ProposalOf<T>>::insert(hash, ProposalRecord {
+				index: legacy.index,
+				author: legacy.author,
+				stage: legacy.stage,
+				transition_time: legacy.transition_time,
+				title: legacy.title,
+				contents_hash: contents_hash,
+				ipfs_cid: None,
+				vote_id: legacy.vote_id,
+				versions: vec![contents_hash],
+				voting_length: voting_length,
+			});
+		}
+		<ProposalsMigrated>::put(true);
+	}
+
+	/// Advances a proposal from the "prevoting" or "commit" stage
+	fn do_advance_proposal(
+		proposal_hash: T::Hash,
+		record: ProposalRecord<T::AccountId, T::BlockNumber, T::Hash>
+	) -> Result {
+		// prevoting -> voting or commit
+		<voting::Module<T>>::advance_stage(record.vote_id)?;
+		if let Some(vote_record) = <voting::Module<T>>::get_vote_record(record.vote_id) {
+			let stage_length = match vote_record.data.stage {
+				VoteStage::Commit => Self::commit_length(),
+				_ => record.voting_length,
+			};
+			let transition_time = <system::Module<T>>::block_number() + stage_length;
+			let vote_id = record.vote_id;
+			<ProposalOf<T>>::insert(proposal_hash, ProposalRecord {
+				stage: vote_record.data.stage,
+				transition_time: transition_time.clone(),
+				..record
+			});
+			<InactiveProposals<T>>::mutate(|proposals| proposals.retain(|x| x.0 != proposal_hash));
+			<ActiveProposals<T>>::mutate(|proposals| proposals.push((proposal_hash, transition_time.clone())));
+
+			// emit event for voting if at this stage
+			if vote_record.data.stage == VoteStage::Voting {
+				Self::deposit_event(RawEvent::VotingStarted(proposal_hash, vote_id, transition_time));
+			}
+
+			// emit event for committing if at this stage
+			if vote_record.data.stage == VoteStage::Commit {
+				Self::deposit_event(RawEvent::CommitStarted(proposal_hash, vote_id, transition_time));
+			}
+			Ok(())
+		} else {
+			Err("Vote record does not exist")
+		}
+	}
+
+	/// Stores a compact summary of a completed proposal, indexed by its index
+	fn archive_proposal(
+		proposal_hash: T::Hash,
+		record: &ProposalRecord<T::AccountId, T::BlockNumber, T::Hash>,
+		vote_record: &voting::VoteRecord<T::AccountId>
+	) {
+		let tally_type = vote_record.data.tally_type;
+		let first_choice_tally = vote_record.outcomes.iter().map(|outcome| {
+			let weight = vote_record.reveals.iter()
+				.filter(|(_, vote)| vote.first() == Some(outcome))
+				.fold(Zero::zero(), |acc: BalanceOf<T>, (who, _)| acc.saturating_add(match tally_type {
+					TallyType::OnePerson => <BalanceOf<T>>::from(1u32),
+					TallyType::OneCoin => T::Currency::total_balance(who),
+				}));
+			(*outcome, weight)
+		}).collect();
+		<ArchivedProposals<T>>::insert(record.index, ArchivedProposal {
+			proposal_hash: proposal_hash,
+			author: record.author.clone(),
+			vote_id: record.vote_id,
+			contents_hash: record.contents_hash,
+			tally_type: tally_type,
+			first_choice_tally: first_choice_tally,
+			completed_at: <system::Module<T>>::block_number(),
+		});
+		Self::deposit_event(RawEvent::ProposalArchived(proposal_hash, record.index));
+	}
+
+	/// Unreserves the deposits of all seconders of a proposal
+	fn return_seconding_deposits(proposal_hash: &T::Hash) {
+		<SecondsOf<T>>::take(proposal_hash).into_iter().for_each(|(who, deposit)| {
+			T::Currency::unreserve(&who, deposit);
+		});
+	}
+
+	/// Removes a proposal record and releases the preimages of all its versions
+	fn remove_proposal(proposal_hash: &T::Hash) {
+		if let Some(record) = <ProposalOf<T>>::take(proposal_hash) {
+			record.versions.iter().for_each(|hash| Self::release_preimage(hash));
+		}
+	}
+
+	/// Drops a reference to a preimage, deleting it and returning the deposit
+	/// once it is no longer used by any proposal
+	fn release_preimage(contents_hash: &T::Hash) {
+		if let Some(mut preimage) = <Preimages<T>>::get(contents_hash) {
+			preimage.uses = preimage.uses.saturating_sub(1);
+			if preimage.uses == 0 {
+				T::Currency::unreserve(&preimage.provider, preimage.deposit);
+				<Preimages<T>>::remove(contents_hash);
+				Self::deposit_event(RawEvent::PreimageCleared(*contents_hash));
+			} else {
+				<Preimages<T>>::insert(contents_hash, preimage);
+			}
+		}
+	}
+}
+
 decl_event!(
 	pub enum Event<T> where <T as system::Trait>::Hash,
 							<T as system::Trait>::AccountId,
-							<T as system::Trait>::BlockNumber {
+							<T as system::Trait>::BlockNumber,
+							Balance = BalanceOf<T> {
 		/// Emitted at proposal creation: (Creator, ProposalHash)
 		NewProposal(AccountId, Hash),
 		/// Emitted when commit stage begins: (ProposalHash, VoteId, CommitEndTime)
@@ -230,6 +636,22 @@ decl_event!(
 		VotingStarted(Hash, u64, BlockNumber),
 		/// Emitted when voting is completed: (ProposalHash, VoteId, VoteResults)
 		VotingCompleted(Hash, u64),
+		/// Emitted when a proposal is seconded: (ProposalHash, Seconder)
+		Seconded(Hash, AccountId),
+		/// Emitted when a proposal is amended: (ProposalHash, ContentsHash, Version)
+		ProposalAmended(Hash, Hash, u32),
+		/// Emitted when a completed proposal is archived: (ProposalHash, ProposalIndex)
+		ProposalArchived(Hash, u32),
+		/// Emitted when the default stage lengths change: (Prevoting, Commit, Voting)
+		StageLengthsChanged(BlockNumber, BlockNumber, BlockNumber),
+		/// Emitted when proposal contents are noted: (ContentsHash, Provider, Deposit)
+		PreimageNoted(Hash, AccountId, Balance),
+		/// Emitted when proposal contents are removed and the deposit returned: (ContentsHash)
+		PreimageCleared(Hash),
+		/// Emitted when the seconding parameters change: (Deposit, RequiredSeconds, RequiredBacking)
+		SecondingParametersChanged(Balance, u32, Balance),
+		/// Emitted when the identity requirement for proposal authors changes: (Required)
+		AuthorIdentityRequiredChanged(bool),
 	}
 );
 
@@ -243,11 +665,39 @@ decl_storage! {
 		pub ActiveProposals get(fn active_proposals): Vec<(T::Hash, T::BlockNumber)>;
 		/// A list of completed proposals, pending deletion
 		pub CompletedProposals get(fn completed_proposals): Vec<(T::Hash, T::BlockNumber)>;
-		/// Amount of time a proposal remains in "Voting" stage.
+		/// Amount of time a proposal remains in "PreVoting" stage before expiring.
+		pub PrevotingLength get(fn prevoting_length) config(): T::BlockNumber;
+		/// Amount of time a proposal remains in "Commit" stage.
+		pub CommitLength get(fn commit_length) config(): T::BlockNumber;
+		/// Default amount of time a proposal remains in "Voting" stage.
 		pub VotingLength get(fn voting_length) config(): T::BlockNumber;
+		/// Minimum voting length an author may pick for a proposal.
+		pub MinVotingLength get(fn min_voting_length) config(): T::BlockNumber;
+		/// Maximum voting length an author may pick for a proposal.
+		pub MaxVotingLength get(fn max_voting_length) config(): T::BlockNumber;
 		/// Map for retrieving the information about any proposal from its hash.
-		pub ProposalOf get(fn proposal_of): map T::Hash => Option<ProposalRecord<T::AccountId, T::BlockNumber>>;
+		pub ProposalOf get(fn proposal_of): map T::Hash => Option<ProposalRecord<T::AccountId, T::BlockNumber, T::Hash>>;
 		/// Registration bond
 		pub ProposalCreationBond get(fn proposal_creation_bond) config(): BalanceOf<T>;
+		/// Summaries of completed proposals, indexed by proposal index.
+		pub ArchivedProposals get(fn archived_proposals): map u32 => Option<ArchivedProposal<T::AccountId, T::BlockNumber, T::Hash, BalanceOf<T>>>;
+		/// Seconders of a proposal along with their reserved deposits.
+		pub SecondsOf get(fn seconds_of): map T::Hash => Vec<(T::AccountId, BalanceOf<T>)>;
+		/// Deposit reserved from each seconder of a proposal.
+		pub SecondingDeposit get(fn seconding_deposit) config(): BalanceOf<T>;
+		/// Number of seconds after which a proposal advances automatically, 0 to disable.
+		pub RequiredSeconds get(fn required_seconds) config(): u32;
+		/// Total reserved by seconders after which a proposal advances automatically, 0 to disable.
+		pub RequiredBacking get(fn required_backing) config(): BalanceOf<T>;
+		/// Noted proposal contents, indexed by their hash.
+		pub Preimages get(fn preimages): map T::Hash => Option<PreimageRecord<T::AccountId, BalanceOf<T>>>;
+		/// Deposit reserved per byte of noted proposal contents.
+		pub PreimageByteDeposit get(fn preimage_byte_deposit) config(): BalanceOf<T>;
+		/// Maximum length in bytes of a proposal title.
+		pub MaxTitleLength get(fn max_title_length) config(): u32 = 256;
+		/// Whether proposal authors must have a verified identity.
+		pub AuthorIdentityRequired get(fn author_identity_required) config(): bool;
+		/// Whether proposals stored in the legacy layout were migrated.
+		pub ProposalsMigrated get(fn proposals_migrated) build(|_: &GenesisConfig<T>| true): bool;
 	}
 }
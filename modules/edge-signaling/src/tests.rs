@@ -20,11 +20,12 @@ use support::{parameter_types, impl_outer_origin, assert_err, assert_ok};
 use substrate_primitives::{H256, Blake2Hasher, Hasher};
 use sr_primitives::{
 	Perbill,
-	traits::{BlakeTwo256, OnFinalize, IdentityLookup},
+	traits::{BlakeTwo256, OnFinalize, OnInitialize, IdentityLookup},
 	testing::{Header}
 };
 pub use crate::{Event, Module, RawEvent, Trait, GenesisConfig};
 use voting::{VoteOutcome, TallyType, VoteStage, VoteType};
+use std::cell::RefCell;
 
 impl_outer_origin! {
 	pub enum Origin for Test {}
@@ -84,16 +85,36 @@ impl voting::Trait for Test {
 	type Event = ();
 }
 
+thread_local! {
+	static VERIFIED: RefCell<Vec<u64>> = RefCell::new(vec![]);
+}
+
+/// Treats the accounts in `VERIFIED` as having a verified github identity.
+pub struct TestIdentityProvider;
+impl IdentityProvider<u64> for TestIdentityProvider {
+	fn is_verified(who: &u64, identity_type: &[u8]) -> bool {
+		identity_type == b"github" && VERIFIED.with(|v| v.borrow().contains(who))
+	}
+
+	fn verified_types(who: &u64) -> Vec<Vec<u8>> {
+		if Self::is_verified(who, b"github") { vec![b"github".to_vec()] } else { vec![] }
+	}
+}
+
 impl Trait for Test {
 	type Event = ();
 	type Currency = balances::Module<Self>;
+	type IdentityProvider = TestIdentityProvider;
 }
 
 pub type Balances = balances::Module<Test>;
 pub type System = system::Module<Test>;
 pub type Signaling = Module<Test>;
+pub type Voting = voting::Module<Test>;
 
 const BOND: u128 = 10;
+const BYTE_DEPOSIT: u128 = 1;
+const SECOND_DEPOSIT: u128 = 5;
 const YES_VOTE: voting::VoteOutcome = [0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,1];
 const NO_VOTE: voting::VoteOutcome = [0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0];
 
@@ -102,8 +123,18 @@ fn new_test_ext() -> sr_io::TestExternalities {
 	// We use default for brevity, but you can configure as desired if needed.
 	t.0.extend(
 		GenesisConfig::<Test> {
+			prevoting_length: 10000,
+			commit_length: 10000,
 			voting_length: 10000,
+			min_voting_length: 100,
+			max_voting_length: 20000,
 			proposal_creation_bond: BOND,
+			preimage_byte_deposit: BYTE_DEPOSIT,
+			max_title_length: 64,
+			author_identity_required: false,
+			seconding_deposit: SECOND_DEPOSIT,
+			required_seconds: 2,
+			required_backing: 0,
 		}.build_storage().unwrap().0,
 	);
 	t.0.extend(
@@ -120,6 +151,10 @@ fn new_test_ext() -> sr_io::TestExternalities {
 	t.into()
 }
 
+fn note_preimage(who: u64, proposal: &[u8]) -> Result {
+	Signaling::note_preimage(Origin::signed(who), proposal.to_vec())
+}
+
 fn propose(
 	who: u64,
 	title: &[u8],
@@ -128,23 +163,45 @@ fn propose(
 	vote_type: VoteType,
 	tally_type: TallyType
 ) -> Result {
+	let contents_hash = build_contents_hash(proposal);
+	if Signaling::preimages(contents_hash).is_none() {
+		note_preimage(who, proposal)?;
+	}
 	Signaling::create_proposal(
 		Origin::signed(who),
 		title.to_vec(),
-		proposal.to_vec(),
+		contents_hash,
+		None,
 		outcomes,
 		vote_type,
-		tally_type)
+		tally_type,
+		None)
+}
+
+fn amend_proposal(who: u64, proposal_hash: H256, proposal: &[u8]) -> Result {
+	let contents_hash = build_contents_hash(proposal);
+	if Signaling::preimages(contents_hash).is_none() {
+		note_preimage(who, proposal)?;
+	}
+	Signaling::amend_proposal(Origin::signed(who), proposal_hash, contents_hash, None)
+}
+
+fn second(who: u64, proposal_hash: H256) -> Result {
+	Signaling::second(Origin::signed(who), proposal_hash, SECOND_DEPOSIT)
 }
 
 fn advance_proposal(who: u64, proposal_hash: H256) -> Result {
 	Signaling::advance_proposal(Origin::signed(who), proposal_hash)
 }
 
+fn build_contents_hash(proposal: &[u8]) -> H256 {
+		return Blake2Hasher::hash(proposal);
+}
+
 fn build_proposal_hash(who: u64, proposal: &[u8]) -> H256 {
 		let mut buf = Vec::new();
 		buf.extend_from_slice(&who.encode());
-		buf.extend_from_slice(proposal.as_ref());
+		buf.extend_from_slice(&build_contents_hash(proposal).encode());
 		return Blake2Hasher::hash(&buf[..]);
 }
 
@@ -163,15 +220,18 @@ fn make_record(
 	author: u64,
 	title: &[u8],
 	contents: &[u8])
-	-> ProposalRecord<u64, u64> {
+	-> ProposalRecord<u64, u64, H256> {
 		ProposalRecord {
 			index: 0,
 			author: author,
 			stage: VoteStage::PreVoting,
 			transition_time: 0,
 			title: title.to_vec(),
-			contents: contents.to_vec(),
+			contents_hash: build_contents_hash(contents),
+			ipfs_cid: None,
 			vote_id: 1,
+			versions: vec![build_contents_hash(contents)],
+			voting_length: 10000,
 		}
 }
 
@@ -246,6 +306,7 @@ fn propose_empty_should_fail() {
 		let hash = build_proposal_hash(public, &proposal);
 		let outcomes = vec![YES_VOTE, NO_VOTE];
 		assert_eq!(propose(public, title, &proposal, outcomes, VoteType::Binary, TallyType::OneCoin), Err("Proposal must not be empty"));
+		assert_eq!(Signaling::preimages(build_contents_hash(&proposal)), None);
 		assert_eq!(Signaling::proposal_count(), 0);
 		assert_eq!(Signaling::inactive_proposals(), vec![]);
 		assert_eq!(Signaling::proposal_of(hash), None);
@@ -426,6 +487,8 @@ fn creating_proposal_with_insufficient_balance_fails() {
 		let (title, proposal) = generate_proposal();
 		let outcomes = vec![YES_VOTE, NO_VOTE];
 
+		assert_err!(note_preimage(public, proposal), "Not enough currency for preimage deposit");
+		assert_ok!(note_preimage(get_test_key(), proposal));
 		assert_err!(
 			propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin),
 			"Not enough currency for reserve bond");
@@ -442,16 +505,18 @@ fn completed_proposal_should_return_creation_bond() {
 		let hash = build_proposal_hash(public, &proposal);
 		let balance = Balances::free_balance(public);
 		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		let deposit = BYTE_DEPOSIT * proposal.len() as u128;
 		let after_propose_balance = Balances::free_balance(public);
-		assert_eq!(balance - BOND, after_propose_balance);
+		assert_eq!(balance - BOND - deposit, after_propose_balance);
 		assert_ok!(advance_proposal(public, hash));
 		println!("{:?}", Signaling::proposal_of(hash));
 		System::set_block_number(10002);
 		<Signaling as OnFinalize<u64>>::on_finalize(10002);
 		System::set_block_number(10003);
 
+		// the preimage deposit stays reserved until the proposal is deleted
 		let after_completion_balance = Balances::free_balance(public);
-		assert_eq!(balance, after_completion_balance);
+		assert_eq!(balance - deposit, after_completion_balance);
 	});
 }
 
@@ -465,8 +530,9 @@ fn expired_inactive_proposal_should_return_creation_bond() {
 		let hash = build_proposal_hash(public, &proposal);
 		let balance = Balances::free_balance(public);
 		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		let deposit = BYTE_DEPOSIT * proposal.len() as u128;
 		let after_propose_balance = Balances::free_balance(public);
-		assert_eq!(balance - BOND, after_propose_balance);
+		assert_eq!(balance - BOND - deposit, after_propose_balance);
 		System::set_block_number(10002);
 		<Signaling as OnFinalize<u64>>::on_finalize(10002);
 		System::set_block_number(10003);
@@ -510,3 +576,622 @@ fn completed_proposal_should_remain_before_deletion() {
 		);
 	});
 }
+
+#[test]
+fn note_preimage_should_reserve_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (_, proposal) = generate_proposal();
+		let contents_hash = build_contents_hash(proposal);
+		let balance = Balances::free_balance(public);
+		assert_ok!(note_preimage(public, proposal));
+		let deposit = BYTE_DEPOSIT * proposal.len() as u128;
+		assert_eq!(Balances::free_balance(public), balance - deposit);
+		assert_eq!(
+			Signaling::preimages(contents_hash),
+			Some(PreimageRecord {
+				contents: proposal.to_vec(),
+				provider: public,
+				deposit: deposit,
+				uses: 0,
+			})
+		);
+		assert_err!(note_preimage(2, proposal), "Preimage already noted");
+	});
+}
+
+#[test]
+fn propose_without_preimage_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_err!(
+			Signaling::create_proposal(
+				Origin::signed(public),
+				title.to_vec(),
+				build_contents_hash(proposal),
+				None,
+				outcomes,
+				VoteType::Binary,
+				TallyType::OneCoin,
+				None),
+			"Proposal preimage not noted"
+		);
+		assert_eq!(Signaling::proposal_count(), 0);
+	});
+}
+
+#[test]
+fn propose_with_long_title_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (_, proposal) = generate_proposal();
+		let title = vec![1u8; 65];
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_err!(
+			propose(public, &title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin),
+			"Proposal title too long"
+		);
+		assert_eq!(Signaling::proposal_count(), 0);
+	});
+}
+
+#[test]
+fn propose_with_ipfs_cid_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let cid = b"QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG".to_vec();
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(note_preimage(public, proposal));
+		assert_ok!(Signaling::create_proposal(
+			Origin::signed(public),
+			title.to_vec(),
+			build_contents_hash(proposal),
+			Some(cid.clone()),
+			outcomes,
+			VoteType::Binary,
+			TallyType::OneCoin,
+			None));
+		assert_eq!(
+			Signaling::proposal_of(hash),
+			Some(ProposalRecord {
+				transition_time: 10001,
+				ipfs_cid: Some(cid),
+				..make_record(public, title, proposal)
+			})
+		);
+	});
+}
+
+#[test]
+fn clear_preimage_in_use_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let contents_hash = build_contents_hash(proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_err!(Signaling::clear_preimage(Origin::signed(2), contents_hash), "Preimage must be cleared by provider");
+		assert_err!(Signaling::clear_preimage(Origin::signed(public), contents_hash), "Preimage is in use");
+	});
+}
+
+#[test]
+fn clear_unused_preimage_should_return_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (_, proposal) = generate_proposal();
+		let contents_hash = build_contents_hash(proposal);
+		let balance = Balances::free_balance(public);
+		assert_ok!(note_preimage(public, proposal));
+		assert_ok!(Signaling::clear_preimage(Origin::signed(public), contents_hash));
+		assert_eq!(Balances::free_balance(public), balance);
+		assert_eq!(Signaling::preimages(contents_hash), None);
+	});
+}
+
+#[test]
+fn deleted_proposal_should_return_preimage_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let contents_hash = build_contents_hash(proposal);
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		let balance = Balances::free_balance(public);
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_ok!(advance_proposal(public, hash));
+		System::set_block_number(10002);
+		<Signaling as OnFinalize<u64>>::on_finalize(10002);
+		assert!(Signaling::preimages(contents_hash).is_some());
+		System::set_block_number(20003);
+		<Signaling as OnFinalize<u64>>::on_finalize(20003);
+		assert_eq!(Signaling::proposal_of(hash), None);
+		assert_eq!(Signaling::preimages(contents_hash), None);
+		assert_eq!(Balances::free_balance(public), balance);
+	});
+}
+
+#[test]
+fn amend_proposal_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		let amended: &[u8] = b"Simple: make Edgeware free for everybody";
+		assert_ok!(amend_proposal(public, hash, amended));
+		assert_eq!(
+			Signaling::proposal_of(hash),
+			Some(ProposalRecord {
+				transition_time: 10001,
+				contents_hash: build_contents_hash(amended),
+				versions: vec![build_contents_hash(proposal), build_contents_hash(amended)],
+				..make_record(public, title, proposal)
+			})
+		);
+		assert_eq!(Signaling::preimages(build_contents_hash(proposal)).unwrap().uses, 1);
+		assert_eq!(Signaling::preimages(build_contents_hash(amended)).unwrap().uses, 1);
+	});
+}
+
+#[test]
+fn amend_proposal_by_non_author_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		let amended: &[u8] = b"Simple: make Edgeware free for everybody";
+		assert_err!(amend_proposal(2, hash, amended), "Proposal must be amended by author");
+	});
+}
+
+#[test]
+fn amend_proposal_to_existing_version_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_err!(amend_proposal(public, hash, proposal), "Proposal version already exists");
+	});
+}
+
+#[test]
+fn amend_proposal_after_advance_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_ok!(advance_proposal(public, hash));
+		let amended: &[u8] = b"Simple: make Edgeware free for everybody";
+		assert_err!(amend_proposal(public, hash, amended), "Proposal not in pre-voting stage");
+	});
+}
+
+#[test]
+fn expired_amended_proposal_should_release_all_versions() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		let balance = Balances::free_balance(public);
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		let amended: &[u8] = b"Simple: make Edgeware free for everybody";
+		assert_ok!(amend_proposal(public, hash, amended));
+		System::set_block_number(10002);
+		<Signaling as OnFinalize<u64>>::on_finalize(10002);
+		assert_eq!(Signaling::proposal_of(hash), None);
+		assert_eq!(Signaling::preimages(build_contents_hash(proposal)), None);
+		assert_eq!(Signaling::preimages(build_contents_hash(amended)), None);
+		assert_eq!(Balances::free_balance(public), balance);
+	});
+}
+
+#[test]
+fn second_should_reserve_deposit() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		let balance = Balances::free_balance(2);
+		assert_ok!(second(2, hash));
+		assert_eq!(Balances::free_balance(2), balance - SECOND_DEPOSIT);
+		assert_eq!(Signaling::seconds_of(hash), vec![(2, SECOND_DEPOSIT)]);
+		assert_eq!(Signaling::proposal_of(hash).unwrap().stage, VoteStage::PreVoting);
+	});
+}
+
+#[test]
+fn second_twice_or_by_author_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_err!(second(public, hash), "Author cannot second own proposal");
+		assert_ok!(second(2, hash));
+		assert_err!(second(2, hash), "Proposal already seconded");
+	});
+}
+
+#[test]
+fn enough_seconds_should_advance_proposal() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_ok!(second(2, hash));
+		assert_ok!(second(3, hash));
+		assert_eq!(Signaling::inactive_proposals(), vec![]);
+		assert_eq!(Signaling::active_proposals(), vec![(hash, 10001)]);
+		assert_eq!(Signaling::proposal_of(hash).unwrap().stage, VoteStage::Voting);
+		assert_err!(second(4, hash), "Proposal not in pre-voting stage");
+	});
+}
+
+#[test]
+fn enough_reserved_backing_should_advance_proposal() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert!(Signaling::set_seconding_parameters(Origin::signed(1), SECOND_DEPOSIT, 0, 30).is_err());
+		assert_ok!(Signaling::set_seconding_parameters(Origin::ROOT, SECOND_DEPOSIT, 0, 30));
+		assert_eq!(Signaling::required_seconds(), 0);
+		assert_eq!(Signaling::required_backing(), 30);
+
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_err!(
+			Signaling::second(Origin::signed(2), hash, SECOND_DEPOSIT - 1),
+			"Seconding amount below deposit"
+		);
+		// Unreserved balances do not count as backing
+		assert_ok!(Signaling::second(Origin::signed(2), hash, 20));
+		assert_ok!(second(3, hash));
+		assert_eq!(Signaling::proposal_of(hash).unwrap().stage, VoteStage::PreVoting);
+		assert_eq!(Balances::reserved_balance(2), 20);
+
+		assert_ok!(Signaling::second(Origin::signed(4), hash, 5));
+		assert_eq!(Signaling::proposal_of(hash).unwrap().stage, VoteStage::Voting);
+	});
+}
+
+#[test]
+fn amend_seconded_proposal_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_ok!(second(2, hash));
+		let amended: &[u8] = b"Simple: make Edgeware free for everybody";
+		assert_err!(amend_proposal(public, hash, amended), "Cannot amend a seconded proposal");
+		assert_eq!(Signaling::proposal_of(hash).unwrap().versions, vec![build_contents_hash(proposal)]);
+	});
+}
+
+#[test]
+fn completed_proposal_should_return_seconding_deposits() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_ok!(second(2, hash));
+		assert_ok!(second(3, hash));
+		System::set_block_number(10002);
+		<Signaling as OnFinalize<u64>>::on_finalize(10002);
+		assert_eq!(Signaling::proposal_of(hash).unwrap().stage, VoteStage::Completed);
+		assert_eq!(Balances::free_balance(2), 100);
+		assert_eq!(Balances::free_balance(3), 100);
+		assert_eq!(Signaling::seconds_of(hash), vec![]);
+	});
+}
+
+#[test]
+fn expired_inactive_proposal_should_return_seconding_deposits() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_ok!(second(2, hash));
+		System::set_block_number(10002);
+		<Signaling as OnFinalize<u64>>::on_finalize(10002);
+		assert_eq!(Signaling::proposal_of(hash), None);
+		assert_eq!(Balances::free_balance(2), 100);
+	});
+}
+
+fn propose_with_length(who: u64, voting_length: u64) -> Result {
+	let (title, proposal) = generate_proposal();
+	assert_ok!(note_preimage(who, proposal));
+	Signaling::create_proposal(
+		Origin::signed(who),
+		title.to_vec(),
+		build_contents_hash(proposal),
+		None,
+		vec![YES_VOTE, NO_VOTE],
+		VoteType::Binary,
+		TallyType::OneCoin,
+		Some(voting_length))
+}
+
+#[test]
+fn propose_with_custom_voting_length_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		assert_ok!(propose_with_length(public, 500));
+		assert_ok!(advance_proposal(public, hash));
+		assert_eq!(
+			Signaling::proposal_of(hash),
+			Some(ProposalRecord {
+				stage: VoteStage::Voting,
+				transition_time: 501,
+				voting_length: 500,
+				..make_record(public, title, proposal)
+			})
+		);
+		System::set_block_number(502);
+		<Signaling as OnFinalize<u64>>::on_finalize(502);
+		assert_eq!(Signaling::proposal_of(hash).unwrap().stage, VoteStage::Completed);
+	});
+}
+
+#[test]
+fn propose_with_out_of_bounds_voting_length_should_fail() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		assert_err!(propose_with_length(public, 99), "Voting length too short");
+		assert_err!(Signaling::create_proposal(
+			Origin::signed(public),
+			b"title".to_vec(),
+			build_contents_hash(generate_proposal().1),
+			None,
+			vec![YES_VOTE, NO_VOTE],
+			VoteType::Binary,
+			TallyType::OneCoin,
+			Some(20001)), "Voting length too long");
+	});
+}
+
+#[test]
+fn set_stage_lengths_should_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert!(Signaling::set_stage_lengths(Origin::signed(1), 10, 20, 300).is_err());
+		assert_err!(Signaling::set_stage_lengths(Origin::ROOT, 10, 20, 30), "Voting length out of bounds");
+		assert_err!(Signaling::set_stage_lengths(Origin::ROOT, 0, 20, 300), "Prevoting length must be positive");
+		assert_err!(Signaling::set_stage_lengths(Origin::ROOT, 10, 0, 300), "Commit length must be positive");
+		assert_ok!(Signaling::set_stage_lengths(Origin::ROOT, 10, 20, 300));
+		assert_eq!(Signaling::prevoting_length(), 10);
+		assert_eq!(Signaling::commit_length(), 20);
+		assert_eq!(Signaling::voting_length(), 300);
+
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_eq!(Signaling::inactive_proposals(), vec![(hash, 11)]);
+		assert_eq!(Signaling::proposal_of(hash).unwrap().voting_length, 300);
+	});
+}
+
+#[test]
+fn set_voting_length_bounds_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_err!(Signaling::set_voting_length_bounds(Origin::ROOT, 200, 100), "Invalid voting length bounds");
+		assert_err!(Signaling::set_voting_length_bounds(Origin::ROOT, 100, 200), "Default voting length out of bounds");
+		assert_ok!(Signaling::set_voting_length_bounds(Origin::ROOT, 1, 50000));
+		assert_eq!(Signaling::min_voting_length(), 1);
+		assert_eq!(Signaling::max_voting_length(), 50000);
+	});
+}
+
+#[test]
+fn completed_proposal_should_be_archived() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		assert_ok!(advance_proposal(public, hash));
+		let vote_id = Signaling::proposal_of(hash).unwrap().vote_id;
+		assert_ok!(Voting::reveal(Origin::signed(2), vote_id, vec![YES_VOTE], None));
+		assert_ok!(Voting::reveal(Origin::signed(3), vote_id, vec![YES_VOTE], None));
+		assert_ok!(Voting::reveal(Origin::signed(4), vote_id, vec![NO_VOTE], None));
+		assert_eq!(Signaling::archived_proposals(0), None);
+		// coin weighting lets the single, richer voter outweigh the others
+		assert_ok!(Balances::transfer(Origin::signed(2), 4, 90));
+
+		System::set_block_number(10002);
+		<Signaling as OnFinalize<u64>>::on_finalize(10002);
+		let archived = Some(ArchivedProposal {
+			proposal_hash: hash,
+			author: public,
+			vote_id: vote_id,
+			contents_hash: build_contents_hash(proposal),
+			tally_type: TallyType::OneCoin,
+			first_choice_tally: vec![(YES_VOTE, 110), (NO_VOTE, 190)],
+			completed_at: 10002,
+		});
+		assert_eq!(Signaling::archived_proposals(0), archived);
+
+		// the summary outlives the deleted proposal record
+		System::set_block_number(20003);
+		<Signaling as OnFinalize<u64>>::on_finalize(20003);
+		assert_eq!(Signaling::proposal_of(hash), None);
+		assert_eq!(Signaling::archived_proposals(0), archived);
+	});
+}
+
+#[test]
+fn one_person_archive_should_count_voters() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let hash = build_proposal_hash(public, &proposal);
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OnePerson));
+		assert_ok!(advance_proposal(public, hash));
+		let vote_id = Signaling::proposal_of(hash).unwrap().vote_id;
+		assert_ok!(Voting::reveal(Origin::signed(2), vote_id, vec![YES_VOTE], None));
+		assert_ok!(Voting::reveal(Origin::signed(3), vote_id, vec![YES_VOTE], None));
+		assert_ok!(Voting::reveal(Origin::signed(4), vote_id, vec![NO_VOTE], None));
+		assert_ok!(Balances::transfer(Origin::signed(2), 4, 90));
+
+		System::set_block_number(10002);
+		<Signaling as OnFinalize<u64>>::on_finalize(10002);
+		let archived = Signaling::archived_proposals(0).unwrap();
+		assert_eq!(archived.tally_type, TallyType::OnePerson);
+		assert_eq!(archived.first_choice_tally, vec![(YES_VOTE, 2), (NO_VOTE, 1)]);
+	});
+}
+
+#[test]
+fn expired_inactive_proposal_should_not_be_archived() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let public = get_test_key();
+		let (title, proposal) = generate_proposal();
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+		System::set_block_number(10002);
+		<Signaling as OnFinalize<u64>>::on_finalize(10002);
+		assert_eq!(Signaling::archived_proposals(0), None);
+	});
+}
+
+#[test]
+fn propose_should_require_verified_identity_when_enabled() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		assert!(Signaling::set_author_identity_required(Origin::signed(1), true).is_err());
+		assert_ok!(Signaling::set_author_identity_required(Origin::ROOT, true));
+		assert!(Signaling::author_identity_required());
+		let public = 1_u64;
+		let title: &[u8] = b"Edgeware";
+		let proposal: &[u8] = b"Proposal";
+		let outcomes = vec![YES_VOTE, NO_VOTE];
+		assert_err!(
+			propose(public, title, proposal, outcomes.clone(), VoteType::Binary, TallyType::OneCoin),
+			"Proposal author must have a verified identity"
+		);
+
+		VERIFIED.with(|v| v.borrow_mut().push(public));
+		assert_ok!(propose(public, title, proposal, outcomes, VoteType::Binary, TallyType::OneCoin));
+	});
+}
+
+#[test]
+fn legacy_proposals_should_migrate() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let (title, proposal) = generate_proposal();
+		let mut buf = Vec::new();
+		buf.extend_from_slice(&1u64.encode());
+		buf.extend_from_slice(proposal);
+		let hash = Blake2Hasher::hash(&buf[..]);
+		unhashed::put(&<ProposalOf<Test>>::hashed_key_for(&hash), &LegacyProposalRecord {
+			index: 0,
+			author: 1u64,
+			stage: VoteStage::PreVoting,
+			transition_time: 10001u64,
+			title: title.to_vec(),
+			contents: proposal.to_vec(),
+			vote_id: 1,
+		});
+		<InactiveProposals<Test>>::put(vec![(hash, 10001)]);
+		<PreimageByteDeposit<Test>>::put(0);
+		<ProposalCreationBond<Test>>::put(100_000_000);
+		<ProposalsMigrated>::put(false);
+
+		<Signaling as OnInitialize<u64>>::on_initialize(1);
+		assert!(Signaling::proposals_migrated());
+		assert_eq!(Signaling::preimage_byte_deposit(), 10);
+		let mut record = make_record(1, title, proposal);
+		record.transition_time = 10001;
+		assert_eq!(Signaling::proposal_of(hash), Some(record));
+		let preimage = Signaling::preimages(build_contents_hash(proposal)).unwrap();
+		assert_eq!(preimage.contents, proposal.to_vec());
+		assert_eq!((preimage.deposit, preimage.uses), (0, 1));
+
+		// migrating again leaves the records untouched
+		<Signaling as OnInitialize<u64>>::on_initialize(2);
+		assert_eq!(Signaling::preimages(build_contents_hash(proposal)).unwrap().uses, 1);
+	});
+}
+
+#[test]
+fn migration_should_default_stage_lengths() {
+	new_test_ext().execute_with(|| {
+		<PrevotingLength<Test>>::put(0);
+		<CommitLength<Test>>::put(0);
+		<MinVotingLength<Test>>::put(0);
+		<MaxVotingLength<Test>>::put(0);
+		<ProposalCreationBond<Test>>::put(1000);
+		<SecondingDeposit<Test>>::put(0);
+		<RequiredSeconds>::put(0);
+		<ProposalsMigrated>::put(false);
+
+		<Signaling as OnInitialize<u64>>::on_initialize(1);
+		assert_eq!(Signaling::seconding_deposit(), 10);
+		assert_eq!(Signaling::required_seconds(), DEFAULT_REQUIRED_SECONDS);
+		<ProposalCreationBond<Test>>::put(BOND);
+		assert_eq!(Signaling::prevoting_length(), 10000);
+		assert_eq!(Signaling::commit_length(), 10000);
+		assert_eq!(Signaling::min_voting_length(), 10000);
+		assert_eq!(Signaling::max_voting_length(), 10000);
+
+		System::set_block_number(1);
+		let (title, proposal) = generate_proposal();
+		assert_ok!(propose(get_test_key(), title, proposal, vec![YES_VOTE, NO_VOTE], VoteType::Binary, TallyType::OneCoin));
+	});
+}
@@ -0,0 +1,90 @@
+// Copyright 2018 Commonwealth Labs, Inc.
+// This file is part of Edgeware.
+
+// Edgeware is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Edgeware is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Edgeware.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definitions for the treasury reward module and the
+//! inflation it contributes to.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Decode, Encode};
+use sr_primitives::{Perbill, RuntimeDebug};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+
+/// Treasury minting to date.
+///
+/// The minted totals count from the runtime upgrade that introduced them,
+/// so mintings of earlier runtimes are not included.
+#[derive(Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct TreasuryMintingInfo<Balance> {
+	/// Total amount minted to all recipients.
+	pub total_minted: Balance,
+	/// Total amount minted to the treasury.
+	pub treasury_minted: Balance,
+	/// Balance of the treasury at the end of the latest block.
+	pub treasury_balance: Balance,
+}
+
+/// Current sources of new issuance and the supply they project.
+#[derive(Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct InflationProjection<BlockNumber, Balance> {
+	/// Number of blocks in a staking era.
+	pub era_length: BlockNumber,
+	/// Amount paid to validators and nominators at the end of an era.
+	pub era_staking_payout: Balance,
+	/// Amount of the era's maximum payout that goes to the treasury instead.
+	pub era_staking_remainder: Balance,
+	/// Amount minted to the treasury reward recipients per block.
+	pub block_treasury_mint: Balance,
+	/// Share of transaction fees that goes to the treasury.
+	pub fee_treasury_share: Perbill,
+	/// Share of transaction fees that goes to the block author.
+	pub fee_author_share: Perbill,
+	/// Current total issuance.
+	pub total_issuance: Balance,
+	/// Number of blocks ahead the supply is projected for.
+	pub blocks: BlockNumber,
+	/// Total issuance after `blocks`, assuming the current rates hold.
+	pub projected_issuance: Balance,
+}
+
+sr_api::decl_runtime_apis! {
+	/// The API to query treasury minting.
+	pub trait TreasuryRewardApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec,
+	{
+		/// Returns the amounts minted to date and the treasury balance.
+		fn treasury_minting() -> TreasuryMintingInfo<Balance>;
+
+		/// Returns the total amount minted to `who` since the minted totals
+		/// were introduced.
+		fn minted_to(who: AccountId) -> Balance;
+	}
+
+	/// The API to project the supply from the current inflation sources.
+	pub trait InflationApi<BlockNumber, Balance> where
+		BlockNumber: Codec,
+		Balance: Codec,
+	{
+		/// Returns the current inflation sources and the supply `blocks` ahead.
+		fn projected_inflation(blocks: BlockNumber) -> InflationProjection<BlockNumber, Balance>;
+	}
+}
@@ -20,56 +20,382 @@
 #[cfg(test)]
 mod tests;
 
-use support::traits::Currency;
+use support::traits::{Currency, EnsureOrigin, Get, Imbalance};
 use rstd::prelude::*;
-use sr_primitives::traits::{Zero};
+use sr_primitives::{Perbill, RuntimeDebug};
+use sr_primitives::helpers_128bit::multiply_by_rational;
+use sr_primitives::traits::{Zero, Saturating, SaturatedConversion};
+use codec::{Decode, Encode};
 
-use support::{decl_event, decl_module, decl_storage};
+use support::{decl_event, decl_module, decl_storage, ensure};
+use support::dispatch::Result;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
 
 pub type BalanceOf<T> = <<T as staking::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
 
+/// How the amount minted for the treasury is determined
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Encode, Decode, Copy, Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum MintingMode {
+	/// Mint the current payout every minting interval
+	Fixed,
+	/// Mint a share of the total issuance every minting interval, prorated
+	/// from the given annual rate
+	Issuance(Perbill),
+}
+
+impl Default for MintingMode {
+	fn default() -> Self {
+		MintingMode::Fixed
+	}
+}
+
 pub trait Trait: staking::Trait + treasury::Trait + balances::Trait {
 	/// The overarching event type.
 	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 	/// The account balance
 	type Currency: Currency<Self::AccountId>;
+	/// Origin from which the minting parameters can be changed.
+	type MintingOrigin: EnsureOrigin<Self::Origin>;
+	/// Number of blocks in a year, used to prorate annual inflation rates.
+	type BlocksPerYear: Get<Self::BlockNumber>;
 }
 
 decl_module! {
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
 		fn deposit_event() = default;
+
+		/// Set the amount minted for the treasury every minting interval.
+		pub fn set_current_payout(origin, payout: BalanceOf<T>) -> Result {
+			T::MintingOrigin::ensure_origin(origin)?;
+			<CurrentPayout<T>>::put(payout);
+			Self::deposit_event(RawEvent::PayoutChanged(payout));
+			Ok(())
+		}
+
+		/// Set the number of blocks between treasury mintings, or zero to
+		/// disable minting.
+		pub fn set_minting_interval(origin, interval: T::BlockNumber) -> Result {
+			T::MintingOrigin::ensure_origin(origin)?;
+			<MintingInterval<T>>::put(interval);
+			Self::deposit_event(RawEvent::MintingIntervalChanged(interval));
+			Ok(())
+		}
+
+		/// Set whether a fixed payout or a share of the total issuance is minted.
+		pub fn set_minting_mode(origin, mode: MintingMode) -> Result {
+			T::MintingOrigin::ensure_origin(origin)?;
+			<Mode>::put(mode);
+			Self::deposit_event(RawEvent::MintingModeChanged(mode));
+			Ok(())
+		}
+
+		/// Set the accounts that share each minting and their weights.
+		///
+		/// Every minting is split between the recipients in proportion to their
+		/// weights. With no recipients, everything is minted to the treasury.
+		pub fn set_recipients(origin, recipients: Vec<(T::AccountId, u32)>) -> Result {
+			T::MintingOrigin::ensure_origin(origin)?;
+			Self::check_recipients(&recipients)?;
+			<Recipients<T>>::put(recipients.clone());
+			Self::deposit_event(RawEvent::RecipientsChanged(recipients));
+			Ok(())
+		}
+
+		/// Replace the schedule of future payout changes.
+		///
+		/// Each `(block, payout)` step sets the current payout once `block` is
+		/// reached. Steps must be sorted by block.
+		pub fn set_payout_schedule(origin, schedule: Vec<(T::BlockNumber, BalanceOf<T>)>) -> Result {
+			T::MintingOrigin::ensure_origin(origin)?;
+			ensure!(schedule.windows(2).all(|w| w[0].0 < w[1].0), "Payout schedule must be sorted by block");
+			<PayoutSchedule<T>>::put(schedule.clone());
+			Self::deposit_event(RawEvent::PayoutScheduleChanged(schedule));
+			Ok(())
+		}
+
+		/// Set the limit on the total amount minted, as counted by `TotalMinted`,
+		/// or remove it with `None`.
+		pub fn set_minting_cap(origin, cap: Option<BalanceOf<T>>) -> Result {
+			T::MintingOrigin::ensure_origin(origin)?;
+			match cap {
+				Some(cap) => <MintingCap<T>>::put(cap),
+				None => <MintingCap<T>>::kill(),
+			}
+			Self::deposit_event(RawEvent::MintingCapChanged(cap));
+			Ok(())
+		}
+
+		/// Set whether the treasury's burn is minted back on top of the payout.
+		pub fn set_burn_offset(origin, enabled: bool) -> Result {
+			T::MintingOrigin::ensure_origin(origin)?;
+			<BurnOffset>::put(enabled);
+			if !enabled {
+				<PendingBurn<T>>::kill();
+				<IssuanceBeforeBurn<T>>::kill();
+			}
+			Self::deposit_event(RawEvent::BurnOffsetChanged(enabled));
+			Ok(())
+		}
+
+		/// Record the treasury's burn at the end of the previous block.
+		fn on_initialize(_n: T::BlockNumber) {
+			Self::record_burn();
+		}
+
 		/// Mint money for the treasury!
 		fn on_finalize(_n: T::BlockNumber) {
-			if <system::Module<T>>::block_number() % Self::minting_interval() == Zero::zero() {
-				let reward = Self::current_payout();
-				<T as staking::Trait>::Currency::deposit_creating(&<treasury::Module<T>>::account_id(), reward);
-				<Pot<T>>::put(<balances::Module<T>>::free_balance(&<treasury::Module<T>>::account_id()));
-				Self::deposit_event(RawEvent::TreasuryMinting(
-					Self::pot(),
-					reward,
-					<system::Module<T>>::block_number())
-				);
+			let now = <system::Module<T>>::block_number();
+			Self::apply_payout_schedule(now);
+			let interval = Self::minting_interval();
+			if !interval.is_zero() {
+				if (now % interval).is_zero() {
+					let pending = <PendingBurn<T>>::take();
+					let reward = Self::payout().saturating_add(pending);
+					let minted = Self::mint_capped(reward, now);
+					// Burn held back by the minting cap stays pending
+					let unminted_burn = pending.min(reward.saturating_sub(minted));
+					if !unminted_burn.is_zero() {
+						<PendingBurn<T>>::put(unminted_burn);
+					}
+				}
+				if Self::burn_offset() && Self::is_spend_period_end(now) {
+					<IssuanceBeforeBurn<T>>::put(<T as staking::Trait>::Currency::total_issuance());
+				}
+			}
+			<Pot<T>>::put(<T as staking::Trait>::Currency::free_balance(&<treasury::Module<T>>::account_id()));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The amount minted at the next minting interval
+	pub fn payout() -> BalanceOf<T> {
+		match Self::minting_mode() {
+			MintingMode::Fixed => Self::current_payout(),
+			MintingMode::Issuance(rate) => {
+				let blocks_per_year = T::BlocksPerYear::get().saturated_into::<u32>();
+				if blocks_per_year == 0 {
+					return Zero::zero();
+				}
+				let interval = Self::minting_interval().saturated_into::<u32>();
+				let annual = rate * <T as staking::Trait>::Currency::total_issuance();
+				annual.saturating_mul(BalanceOf::<T>::from(interval)) / BalanceOf::<T>::from(blocks_per_year)
+			},
+		}
+	}
+
+	/// Returns whether the treasury spends and burns its funds at the end of block `now`
+	fn is_spend_period_end(now: T::BlockNumber) -> bool {
+		let spend_period = <T as treasury::Trait>::SpendPeriod::get();
+		!spend_period.is_zero() && (now % spend_period).is_zero()
+	}
+
+	/// Adds the treasury's burn to the pending offset after a spend period ends
+	///
+	/// The burn is measured as the drop in total issuance since this module
+	/// finalized the previous block. This module finalizes before the
+	/// treasury, and the treasury's spends do not change the issuance, so
+	/// the drop is exactly what the treasury burnt.
+	fn record_burn() {
+		let before = match <IssuanceBeforeBurn<T>>::take() {
+			Some(before) => before,
+			None => return,
+		};
+		let burn = before.saturating_sub(<T as staking::Trait>::Currency::total_issuance());
+		<PendingBurn<T>>::mutate(|pending| *pending = pending.saturating_add(burn));
+	}
+
+	/// Mints `reward`, or as much of it as the minting cap still allows, and
+	/// returns the amount minted
+	fn mint_capped(reward: BalanceOf<T>, now: T::BlockNumber) -> BalanceOf<T> {
+		let cap = match Self::minting_cap() {
+			Some(cap) => cap,
+			None => return Self::mint(reward, now),
+		};
+		let allowed = cap.saturating_sub(Self::total_minted());
+		if allowed.is_zero() {
+			return Zero::zero();
+		}
+		let minted = Self::mint(reward.min(allowed), now);
+		if Self::total_minted() >= cap {
+			Self::deposit_event(RawEvent::MintingCapReached(Self::total_minted()));
+		}
+		minted
+	}
+
+	/// Splits `reward` between the recipients in proportion to their weights
+	/// and returns the amount minted
+	///
+	/// Rounding dust goes to the last recipient with a non-zero weight. Less
+	/// than `reward` is minted when a share would create an account below
+	/// the existential deposit.
+	fn mint(reward: BalanceOf<T>, now: T::BlockNumber) -> BalanceOf<T> {
+		let treasury = <treasury::Module<T>>::account_id();
+		let mut recipients = Self::recipients();
+		recipients.retain(|(_, weight)| *weight > 0);
+		if recipients.is_empty() {
+			recipients.push((treasury.clone(), 1));
+		}
+		let total_weight = recipients.iter().fold(0u128, |sum, (_, weight)| sum + *weight as u128);
+		let last = recipients.len() - 1;
+		let mut remaining = reward;
+		let mut total_minted: BalanceOf<T> = Zero::zero();
+		for (i, (who, weight)) in recipients.into_iter().enumerate() {
+			let amount = if i == last {
+				remaining
+			} else {
+				multiply_by_rational(reward.saturated_into::<u128>(), weight as u128, total_weight)
+					.map(|share| share.saturated_into::<BalanceOf<T>>().min(remaining))
+					.unwrap_or_else(|_| Zero::zero())
+			};
+			remaining = remaining.saturating_sub(amount);
+			let minted = <T as staking::Trait>::Currency::deposit_creating(&who, amount).peek();
+			total_minted = total_minted.saturating_add(minted);
+			<TotalMinted<T>>::mutate(|total| *total = total.saturating_add(minted));
+			<MintedTo<T>>::mutate(&who, |total| *total = total.saturating_add(minted));
+			let pot = <T as staking::Trait>::Currency::free_balance(&who);
+			<RecipientPot<T>>::insert(&who, pot);
+			if who == treasury {
+				<Pot<T>>::put(pot);
+				Self::deposit_event(RawEvent::TreasuryMinting(pot, minted, now));
+			} else {
+				Self::deposit_event(RawEvent::RecipientMinting(who, pot, minted, now));
 			}
 		}
+		total_minted
+	}
+
+	/// Ensures a recipient list can split a minting
+	fn check_recipients(recipients: &[(T::AccountId, u32)]) -> Result {
+		if recipients.is_empty() {
+			return Ok(());
+		}
+		ensure!(recipients.iter().any(|(_, weight)| *weight > 0), "Recipient weights must not all be zero");
+		for (i, (who, _)) in recipients.iter().enumerate() {
+			ensure!(!recipients[..i].iter().any(|(other, _)| other == who), "Recipients must be unique");
+		}
+		Ok(())
+	}
+
+	/// The amount minted per block on average at the current payout
+	pub fn payout_per_block() -> BalanceOf<T> {
+		let interval = Self::minting_interval().saturated_into::<u32>();
+		if interval == 0 {
+			return Zero::zero();
+		}
+		Self::payout() / BalanceOf::<T>::from(interval)
+	}
+
+	/// The amount minted over the next `blocks` at the current payout, up to
+	/// the minting cap
+	///
+	/// Scheduled payout changes and compounding in issuance mode are ignored.
+	pub fn projected_minting(blocks: T::BlockNumber) -> BalanceOf<T> {
+		let interval = Self::minting_interval();
+		if interval.is_zero() {
+			return Zero::zero();
+		}
+		let mintings = (blocks / interval).saturated_into::<u32>();
+		let minted = Self::payout().saturating_mul(BalanceOf::<T>::from(mintings));
+		match Self::minting_cap() {
+			Some(cap) => minted.min(cap.saturating_sub(Self::total_minted())),
+			None => minted,
+		}
+	}
+
+	/// Sets the current payout to the last scheduled step that was reached
+	fn apply_payout_schedule(now: T::BlockNumber) {
+		let schedule = Self::payout_schedule();
+		let reached = schedule.iter().take_while(|(block, _)| *block <= now).count();
+		if reached == 0 {
+			return;
+		}
+		let payout = schedule[reached - 1].1;
+		<PayoutSchedule<T>>::put(schedule[reached..].to_vec());
+		<CurrentPayout<T>>::put(payout);
+		Self::deposit_event(RawEvent::PayoutChanged(payout));
 	}
 }
 
 decl_event!(
 	pub enum Event<T> where <T as system::Trait>::BlockNumber,
-							Balance2 = <<T as staking::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance,
-							Balance = <T as balances::Trait>::Balance
+							<T as system::Trait>::AccountId,
+							Balance = <<T as staking::Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance
 							{
-		TreasuryMinting(Balance, Balance2, BlockNumber),
+		/// The treasury was minted an amount, with its new balance
+		TreasuryMinting(Balance, Balance, BlockNumber),
+		/// A recipient other than the treasury was minted its share, with its
+		/// new balance
+		RecipientMinting(AccountId, Balance, Balance, BlockNumber),
+		/// The lifetime limit on minting changed
+		MintingCapChanged(Option<Balance>),
+		/// Minting stopped because the lifetime limit was reached, with the
+		/// total minted
+		MintingCapReached(Balance),
+		/// Whether the treasury's burn is minted back changed
+		BurnOffsetChanged(bool),
+		/// The accounts sharing each minting changed
+		RecipientsChanged(Vec<(AccountId, u32)>),
+		/// The payout minted every minting interval changed
+		PayoutChanged(Balance),
+		/// The schedule of future payout changes was replaced
+		PayoutScheduleChanged(Vec<(BlockNumber, Balance)>),
+		/// The number of blocks between mintings changed
+		MintingIntervalChanged(BlockNumber),
+		/// The way the minted amount is determined changed
+		MintingModeChanged(MintingMode),
 	}
 );
 
 decl_storage! {
 	trait Store for Module<T: Trait> as TreasuryReward {
-		/// Interval in number of blocks to reward treasury
+		/// Interval in number of blocks to reward treasury; zero disables minting
 		pub MintingInterval get(fn minting_interval) config(): T::BlockNumber;
 		/// Current payout of module
 		pub CurrentPayout get(fn current_payout) config(): BalanceOf<T>;
-		/// Current pot
-		pub Pot get(fn pot): T::Balance;
+		/// Whether a fixed payout or a share of the total issuance is minted
+		pub Mode get(fn minting_mode) config(): MintingMode;
+		/// Future payout changes as `(block, payout)` steps, sorted by block
+		pub PayoutSchedule get(fn payout_schedule) config(): Vec<(T::BlockNumber, BalanceOf<T>)>;
+		/// Accounts sharing each minting with their weights; empty mints
+		/// everything to the treasury
+		pub Recipients get(fn recipients) config(): Vec<(T::AccountId, u32)>;
+		/// Limit on `TotalMinted`, if any
+		pub MintingCap get(fn minting_cap): Option<BalanceOf<T>>;
+		/// Whether the treasury's burn is minted back on top of the payout, so
+		/// that the payout is the net issuance
+		pub BurnOffset get(fn burn_offset) config(): bool;
+		/// Treasury burn not yet minted back
+		pub PendingBurn get(fn pending_burn): BalanceOf<T>;
+		/// Total issuance before the treasury burnt its funds at the end of
+		/// the latest block, until the burn is recorded
+		pub IssuanceBeforeBurn get(fn issuance_before_burn): Option<BalanceOf<T>>;
+		/// Balance of the treasury at the end of the latest block
+		pub Pot get(fn pot): BalanceOf<T>;
+		/// Balance of each recipient after it was last minted its share
+		pub RecipientPot get(fn recipient_pot): map T::AccountId => BalanceOf<T>;
+		/// Total amount minted by this module since these totals were
+		/// introduced; mintings of earlier runtimes are not counted
+		pub TotalMinted get(fn total_minted): BalanceOf<T>;
+		/// Total amount minted to each recipient since these totals were
+		/// introduced; mintings of earlier runtimes are not counted
+		pub MintedTo get(fn minted_to): map T::AccountId => BalanceOf<T>;
+	}
+	add_extra_genesis {
+		config(minting_cap): Option<BalanceOf<T>>;
+		build(|config: &GenesisConfig<T>| {
+			if let Some(cap) = config.minting_cap {
+				<MintingCap<T>>::put(cap);
+			}
+			assert!(
+				config.payout_schedule.windows(2).all(|w| w[0].0 < w[1].0),
+				"Payout schedule must be sorted by block",
+			);
+			if let Err(e) = <Module<T>>::check_recipients(&config.recipients) {
+				panic!("Invalid treasury reward recipients: {}", e);
+			}
+		});
 	}
 }
@@ -26,12 +26,12 @@ use std::{collections::HashSet, cell::RefCell};
 use substrate_primitives::{H256, crypto::key_types};
 
 
-use support::{parameter_types, impl_outer_origin};
+use support::{parameter_types, impl_outer_origin, assert_err, assert_ok};
 
 use sr_primitives::{
 	Perbill, Permill, KeyTypeId,
 	testing::{Header},
-	traits::{OnFinalize, IdentityLookup, One},
+	traits::{OnFinalize, OnInitialize, IdentityLookup, One},
 };
 
 use crate::GenesisConfig;
@@ -117,8 +117,14 @@ impl system::Trait for Test {
 	type Version = ();
 }
 
+pub struct ExistentialDeposit;
+impl Get<u128> for ExistentialDeposit {
+	fn get() -> u128 {
+		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow()) as u128
+	}
+}
+
 parameter_types! {
-	pub const ExistentialDeposit: u128 = 0;
 	pub const TransferFee: u128 = 0;
 	pub const CreationFee: u128 = 0;
 }
@@ -222,9 +228,15 @@ impl treasury::Trait for Test {
 	type Burn = Burn;
 }
 
+parameter_types! {
+	pub const BlocksPerYear: u64 = 1000;
+}
+
 impl Trait for Test {
 	type Event = ();
 	type Currency = Balances;
+	type MintingOrigin = system::EnsureRoot<u64>;
+	type BlocksPerYear = BlocksPerYear;
 }
 
 pub type Balances = balances::Module<Test>;
@@ -236,18 +248,41 @@ pub type TreasuryReward = Module<Test>;
 
 pub struct ExtBuilder {
 	existential_deposit: u64,
+	minting_interval: u64,
+	payout_schedule: Vec<(u64, u128)>,
+	recipients: Vec<(u64, u32)>,
 }
 
 impl Default for ExtBuilder {
 	fn default() -> Self {
 		Self {
 			existential_deposit: 0,
+			minting_interval: One::one(),
+			payout_schedule: vec![],
+			recipients: vec![],
 		}
 	}
 }
 
 impl ExtBuilder {
+	fn existential_deposit(mut self, existential_deposit: u64) -> Self {
+		self.existential_deposit = existential_deposit;
+		self
+	}
+	fn minting_interval(mut self, interval: u64) -> Self {
+		self.minting_interval = interval;
+		self
+	}
+	fn payout_schedule(mut self, schedule: Vec<(u64, u128)>) -> Self {
+		self.payout_schedule = schedule;
+		self
+	}
+	fn recipients(mut self, recipients: Vec<(u64, u32)>) -> Self {
+		self.recipients = recipients;
+		self
+	}
 	fn build(self) -> sr_io::TestExternalities {
+		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = self.existential_deposit);
 		let balance_factor = if self.existential_deposit > 0 {
 			256
 		} else {
@@ -292,7 +327,12 @@ impl ExtBuilder {
 		t.0.extend(
 			GenesisConfig::<Test> {
 				current_payout: 9500000,
-				minting_interval: One::one(),
+				minting_interval: self.minting_interval,
+				payout_schedule: self.payout_schedule,
+				recipients: self.recipients,
+				minting_cap: None,
+				burn_offset: false,
+				minting_mode: MintingMode::Fixed,
 			}.build_storage().unwrap().0,
 		);
 		t.into()
@@ -321,3 +361,401 @@ fn basic_setup_works() {
 		assert_eq!(Balances::free_balance(treasury_address) > 0, true);
 	});
 }
+
+fn mint_at(n: u64) {
+	System::set_block_number(n);
+	<TreasuryReward as OnFinalize<u64>>::on_finalize(n);
+}
+
+/// Runs the hooks of a block, finalizing the treasury after this module
+fn run_block(n: u64) {
+	System::set_block_number(n);
+	<TreasuryReward as OnInitialize<u64>>::on_initialize(n);
+	<TreasuryReward as OnFinalize<u64>>::on_finalize(n);
+	<Treasury as OnFinalize<u64>>::on_finalize(n);
+}
+
+#[test]
+fn set_current_payout_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert!(TreasuryReward::set_current_payout(Origin::signed(1), 1000).is_err());
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1000));
+		assert_eq!(TreasuryReward::current_payout(), 1000);
+
+		let before = Balances::free_balance(treasury_address);
+		mint_at(1);
+		assert_eq!(Balances::free_balance(treasury_address), before + 1000);
+	});
+}
+
+#[test]
+fn set_minting_interval_should_work() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert!(TreasuryReward::set_minting_interval(Origin::signed(1), 10).is_err());
+		assert_ok!(TreasuryReward::set_minting_interval(Origin::ROOT, 10));
+		assert_eq!(TreasuryReward::minting_interval(), 10);
+
+		let before = Balances::free_balance(treasury_address);
+		mint_at(9);
+		assert_eq!(Balances::free_balance(treasury_address), before);
+		mint_at(10);
+		assert_eq!(Balances::free_balance(treasury_address), before + 9500000);
+	});
+}
+
+#[test]
+fn payout_schedule_should_change_payout() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert_err!(
+			TreasuryReward::set_payout_schedule(Origin::ROOT, vec![(10, 100), (5, 50)]),
+			"Payout schedule must be sorted by block"
+		);
+		assert_ok!(TreasuryReward::set_payout_schedule(Origin::ROOT, vec![(5, 500), (10, 100), (20, 0)]));
+
+		mint_at(4);
+		assert_eq!(TreasuryReward::current_payout(), 9500000);
+		mint_at(5);
+		assert_eq!(TreasuryReward::current_payout(), 500);
+		assert_eq!(TreasuryReward::payout_schedule(), vec![(10, 100), (20, 0)]);
+
+		// Steps that were skipped over are applied in order
+		let before = Balances::free_balance(treasury_address);
+		mint_at(25);
+		assert_eq!(TreasuryReward::current_payout(), 0);
+		assert_eq!(TreasuryReward::payout_schedule(), vec![]);
+		assert_eq!(Balances::free_balance(treasury_address), before);
+	});
+}
+
+#[test]
+fn issuance_mode_should_prorate_annual_rate() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert!(TreasuryReward::set_minting_mode(Origin::signed(1), MintingMode::Issuance(Perbill::from_percent(10))).is_err());
+		assert_ok!(TreasuryReward::set_minting_mode(Origin::ROOT, MintingMode::Issuance(Perbill::from_percent(10))));
+		assert_ok!(TreasuryReward::set_minting_interval(Origin::ROOT, 10));
+
+		// 10% a year over 10 of 1000 blocks is 0.1% of the issuance
+		let issuance = Balances::total_issuance();
+		assert_eq!(TreasuryReward::payout(), issuance / 1000);
+		let treasury_address = Treasury::account_id();
+		let before = Balances::free_balance(treasury_address);
+		mint_at(10);
+		assert_eq!(Balances::free_balance(treasury_address), before + issuance / 1000);
+		assert_eq!(Balances::total_issuance(), issuance + issuance / 1000);
+	});
+}
+
+#[test]
+fn issuance_mode_should_compound_over_a_year() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(TreasuryReward::set_minting_mode(Origin::ROOT, MintingMode::Issuance(Perbill::from_percent(10))));
+		assert_ok!(TreasuryReward::set_minting_interval(Origin::ROOT, 10));
+
+		let initial = Balances::total_issuance();
+		for n in 1..=BlocksPerYear::get() {
+			mint_at(n);
+		}
+		let minted = Balances::total_issuance() - initial;
+		// 100 mintings of 0.1% compound to (1.001)^100 - 1, about 10.51%
+		assert!(minted > initial / 10);
+		assert!(minted < initial * 1052 / 10000);
+		assert!(minted > initial * 1051 / 10000);
+	});
+}
+
+#[test]
+fn set_recipients_should_validate() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert!(TreasuryReward::set_recipients(Origin::signed(1), vec![(50, 1)]).is_err());
+		assert_err!(
+			TreasuryReward::set_recipients(Origin::ROOT, vec![(50, 0), (51, 0)]),
+			"Recipient weights must not all be zero"
+		);
+		assert_err!(
+			TreasuryReward::set_recipients(Origin::ROOT, vec![(50, 1), (51, 1), (50, 2)]),
+			"Recipients must be unique"
+		);
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![(50, 1), (51, 1)]));
+		assert_eq!(TreasuryReward::recipients(), vec![(50, 1), (51, 1)]);
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![]));
+		assert_eq!(TreasuryReward::recipients(), vec![]);
+	});
+}
+
+#[test]
+fn minting_should_split_between_recipients() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1000));
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![(treasury_address, 6), (50, 3), (51, 1)]));
+
+		let before = Balances::free_balance(treasury_address);
+		mint_at(1);
+		assert_eq!(Balances::free_balance(treasury_address), before + 600);
+		assert_eq!(Balances::free_balance(50), 300);
+		assert_eq!(Balances::free_balance(51), 100);
+		assert_eq!(TreasuryReward::pot(), before + 600);
+		assert_eq!(TreasuryReward::recipient_pot(treasury_address), before + 600);
+		assert_eq!(TreasuryReward::recipient_pot(50), 300);
+		assert_eq!(TreasuryReward::recipient_pot(51), 100);
+
+		// The treasury's pot is untouched when it is not a recipient
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![(50, 1)]));
+		mint_at(2);
+		assert_eq!(Balances::free_balance(treasury_address), before + 600);
+		assert_eq!(TreasuryReward::pot(), before + 600);
+		assert_eq!(TreasuryReward::recipient_pot(50), 1300);
+	});
+}
+
+#[test]
+fn minting_should_give_rounding_dust_to_last_recipient() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1000));
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![(50, 1), (51, 1), (52, 1)]));
+
+		let issuance = Balances::total_issuance();
+		mint_at(1);
+		assert_eq!(Balances::free_balance(50), 333);
+		assert_eq!(Balances::free_balance(51), 333);
+		assert_eq!(Balances::free_balance(52), 334);
+		assert_eq!(Balances::total_issuance(), issuance + 1000);
+	});
+}
+
+#[test]
+fn minting_should_give_rounding_dust_to_weighted_recipient() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1001));
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![(50, 1), (51, 1), (52, 0)]));
+
+		mint_at(1);
+		assert_eq!(Balances::free_balance(50), 500);
+		assert_eq!(Balances::free_balance(51), 501);
+		assert_eq!(Balances::free_balance(52), 0);
+		assert_eq!(TreasuryReward::minted_to(52), 0);
+	});
+}
+
+#[test]
+fn minting_should_only_count_shares_actually_minted() {
+	ExtBuilder::default().existential_deposit(100).build().execute_with(|| {
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 10010));
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![(50, 1), (51, 1000)]));
+		assert_ok!(TreasuryReward::set_minting_cap(Origin::ROOT, Some(100_000)));
+
+		let issuance = Balances::total_issuance();
+		mint_at(1);
+		// The share of 50 is below the existential deposit of a new account
+		assert_eq!(Balances::free_balance(50), 0);
+		assert_eq!(Balances::free_balance(51), 10000);
+		assert_eq!(TreasuryReward::total_minted(), 10000);
+		assert_eq!(Balances::total_issuance(), issuance + 10000);
+	});
+}
+
+#[test]
+fn minting_should_split_large_payouts_exactly() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1_000_000_000_000));
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![(50, 1), (51, 2)]));
+
+		mint_at(1);
+		assert_eq!(Balances::free_balance(50), 333_333_333_333);
+		assert_eq!(Balances::free_balance(51), 666_666_666_667);
+	});
+}
+
+#[test]
+fn minting_should_track_totals() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1000));
+		mint_at(1);
+		mint_at(2);
+		assert_eq!(TreasuryReward::total_minted(), 2000);
+		assert_eq!(TreasuryReward::minted_to(treasury_address), 2000);
+
+		assert_ok!(TreasuryReward::set_recipients(Origin::ROOT, vec![(treasury_address, 1), (50, 1)]));
+		mint_at(3);
+		assert_eq!(TreasuryReward::total_minted(), 3000);
+		assert_eq!(TreasuryReward::minted_to(treasury_address), 2500);
+		assert_eq!(TreasuryReward::minted_to(50), 500);
+	});
+}
+
+#[test]
+fn pot_should_follow_treasury_balance_between_mintings() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert_ok!(TreasuryReward::set_minting_interval(Origin::ROOT, 10));
+		mint_at(1);
+		assert_eq!(TreasuryReward::pot(), Balances::free_balance(treasury_address));
+
+		assert_ok!(Balances::transfer(Origin::signed(1), treasury_address, 1000));
+		mint_at(2);
+		assert_eq!(TreasuryReward::total_minted(), 0);
+		assert_eq!(TreasuryReward::pot(), Balances::free_balance(treasury_address));
+	});
+}
+
+#[test]
+fn minting_should_stop_at_cap() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1000));
+		assert!(TreasuryReward::set_minting_cap(Origin::signed(1), Some(2500)).is_err());
+		assert_ok!(TreasuryReward::set_minting_cap(Origin::ROOT, Some(2500)));
+
+		let before = Balances::free_balance(treasury_address);
+		mint_at(1);
+		mint_at(2);
+		mint_at(3);
+		assert_eq!(TreasuryReward::total_minted(), 2500);
+		assert_eq!(Balances::free_balance(treasury_address), before + 2500);
+		mint_at(4);
+		assert_eq!(TreasuryReward::total_minted(), 2500);
+		assert_eq!(Balances::free_balance(treasury_address), before + 2500);
+
+		// Raising or removing the cap resumes minting
+		assert_ok!(TreasuryReward::set_minting_cap(Origin::ROOT, None));
+		mint_at(5);
+		assert_eq!(TreasuryReward::total_minted(), 3500);
+	});
+}
+
+#[test]
+fn burn_offset_should_mint_back_treasury_burn() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1000));
+		assert!(TreasuryReward::set_burn_offset(Origin::signed(1), true).is_err());
+		assert_ok!(TreasuryReward::set_burn_offset(Origin::ROOT, true));
+
+		run_block(1);
+		let issuance = Balances::total_issuance();
+		let pot = Balances::free_balance(treasury_address);
+
+		// The spend period ends at block 2, burning half of the pot
+		run_block(2);
+		let burn = issuance + 1000 - Balances::total_issuance();
+		assert!(burn > 0);
+		assert_eq!(TreasuryReward::pending_burn(), 0);
+
+		// The burn is minted back with the next payout, so the issuance only
+		// grows by the payout
+		run_block(3);
+		assert_eq!(TreasuryReward::pending_burn(), 0);
+		assert_eq!(TreasuryReward::issuance_before_burn(), None);
+		assert_eq!(Balances::free_balance(treasury_address), pot + 1000 - burn + 1000 + burn);
+		assert_eq!(Balances::total_issuance(), issuance + 2000);
+
+		// Pending burn is minted at the next minting interval
+		assert_ok!(TreasuryReward::set_minting_interval(Origin::ROOT, 5));
+		let issuance = Balances::total_issuance();
+		run_block(4);
+		let next_burn = issuance - Balances::total_issuance();
+		assert_eq!(TreasuryReward::pending_burn(), 0);
+		run_block(5);
+		assert_eq!(TreasuryReward::pending_burn(), 0);
+		assert_eq!(Balances::total_issuance(), issuance + 1000);
+		assert_eq!(TreasuryReward::total_minted(), 4000 + burn + next_burn);
+	});
+}
+
+#[test]
+fn burn_offset_should_keep_burn_held_back_by_cap() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1000));
+		assert_ok!(TreasuryReward::set_burn_offset(Origin::ROOT, true));
+		assert_ok!(TreasuryReward::set_minting_cap(Origin::ROOT, Some(1500)));
+
+		run_block(1);
+		let issuance = Balances::total_issuance();
+		run_block(2);
+		let burn = issuance + 500 - Balances::total_issuance();
+		assert!(burn > 0);
+		run_block(3);
+		assert_eq!(TreasuryReward::total_minted(), 1500);
+		assert_eq!(TreasuryReward::pending_burn(), burn);
+
+		// The burn is minted back once the cap allows it
+		assert_ok!(TreasuryReward::set_minting_cap(Origin::ROOT, None));
+		mint_at(4);
+		assert_eq!(TreasuryReward::pending_burn(), 0);
+		assert_eq!(TreasuryReward::total_minted(), 2500 + burn);
+	});
+}
+
+#[test]
+fn zero_minting_interval_should_disable_minting() {
+	ExtBuilder::default().minting_interval(0).build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		assert_ok!(TreasuryReward::set_burn_offset(Origin::ROOT, true));
+		let before = Balances::free_balance(treasury_address);
+		for n in 1..=10 {
+			mint_at(n);
+		}
+		assert_eq!(Balances::free_balance(treasury_address), before);
+		assert_eq!(TreasuryReward::total_minted(), 0);
+		assert_eq!(TreasuryReward::pending_burn(), 0);
+
+		assert_ok!(TreasuryReward::set_minting_mode(Origin::ROOT, MintingMode::Issuance(Perbill::from_percent(10))));
+		assert_eq!(TreasuryReward::payout(), 0);
+	});
+}
+
+#[test]
+fn minting_interval_changes_should_apply_mid_chain() {
+	ExtBuilder::default().build().execute_with(|| {
+		let treasury_address = Treasury::account_id();
+		let before = Balances::free_balance(treasury_address);
+		mint_at(1);
+		mint_at(2);
+		assert_eq!(Balances::free_balance(treasury_address), before + 2 * 9500000);
+
+		// Disabling stops minting until an interval is set again
+		assert_ok!(TreasuryReward::set_minting_interval(Origin::ROOT, 0));
+		mint_at(3);
+		mint_at(4);
+		assert_eq!(Balances::free_balance(treasury_address), before + 2 * 9500000);
+
+		assert_ok!(TreasuryReward::set_minting_interval(Origin::ROOT, 3));
+		mint_at(5);
+		assert_eq!(Balances::free_balance(treasury_address), before + 2 * 9500000);
+		mint_at(6);
+		assert_eq!(Balances::free_balance(treasury_address), before + 3 * 9500000);
+	});
+}
+
+#[test]
+#[should_panic(expected = "Payout schedule must be sorted by block")]
+fn genesis_should_reject_unsorted_payout_schedule() {
+	ExtBuilder::default().payout_schedule(vec![(10, 100), (5, 50)]).build();
+}
+
+#[test]
+#[should_panic(expected = "Recipients must be unique")]
+fn genesis_should_reject_duplicate_recipients() {
+	ExtBuilder::default().recipients(vec![(50, 1), (50, 1)]).build();
+}
+
+#[test]
+fn projected_minting_should_respect_interval_and_cap() {
+	ExtBuilder::default().minting_interval(10).build().execute_with(|| {
+		assert_ok!(TreasuryReward::set_current_payout(Origin::ROOT, 1000));
+		assert_eq!(TreasuryReward::payout_per_block(), 100);
+		assert_eq!(TreasuryReward::projected_minting(35), 3000);
+
+		mint_at(10);
+		assert_ok!(TreasuryReward::set_minting_cap(Origin::ROOT, Some(2500)));
+		assert_eq!(TreasuryReward::projected_minting(35), 1500);
+
+		assert_ok!(TreasuryReward::set_minting_interval(Origin::ROOT, 0));
+		assert_eq!(TreasuryReward::payout_per_block(), 0);
+		assert_eq!(TreasuryReward::projected_minting(35), 0);
+	});
+}
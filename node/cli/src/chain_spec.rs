@@ -24,6 +24,7 @@ use edgeware_runtime::{
 	GrandpaConfig, ImOnlineConfig, IndicesConfig, SessionConfig, SessionKeys, StakerStatus, StakingConfig, SudoConfig,
 	SystemConfig, WASM_BINARY,
 	IdentityConfig, SignalingConfig, TreasuryRewardConfig,
+	MintingMode, default_identity_types,
 };
 use edgeware_runtime::Block;
 use edgeware_runtime::constants::currency::*;
@@ -329,14 +330,41 @@ pub fn testnet_genesis(
 			verifiers: crate::testnet_fixtures::get_testnet_identity_verifiers(),
 			expiration_length: 1 * DAYS,
 			registration_bond: 1 * DOLLARS,
+			verification_quorum: 1,
+			verification_fee: 10 * CENTS,
+			verifier_slash_share: Perbill::from_percent(50),
+			dispute_window: 2 * DAYS,
+			appeal_deposit: 1 * DOLLARS,
+			appeal_review_period: 7 * DAYS,
+			verification_validity: 180 * DAYS,
+			max_avatar_length: 256,
+			max_display_name_length: 64,
+			max_tagline_length: 256,
+			metadata_byte_deposit: 10 * MILLICENTS,
+			identity_types: default_identity_types(),
 		}),
 		signaling: Some(SignalingConfig {
+			prevoting_length: 3 * DAYS,
+			commit_length: 3 * DAYS,
 			voting_length: 3 * DAYS,
+			min_voting_length: 1 * DAYS,
+			max_voting_length: 30 * DAYS,
 			proposal_creation_bond: 100 * DOLLARS,
+			preimage_byte_deposit: 1 * MILLICENTS,
+			max_title_length: 256,
+			author_identity_required: false,
+			seconding_deposit: 1 * DOLLARS,
+			required_seconds: 5,
+			required_backing: 0,
 		}),
 		treasury_reward: Some(TreasuryRewardConfig {
 			current_payout: 95 * DOLLARS,
 			minting_interval: One::one(),
+			payout_schedule: vec![],
+			recipients: vec![],
+			minting_cap: None,
+			burn_offset: false,
+			minting_mode: MintingMode::Fixed,
 		}),
 	}
 }
@@ -0,0 +1,74 @@
+// Copyright 2018 Commonwealth Labs, Inc.
+// This file is part of Edgeware.
+
+// Edgeware is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Edgeware is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Edgeware.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface for projecting inflation and treasury income.
+
+use std::sync::Arc;
+
+use edgeware_primitives::{Balance, Block, BlockNumber};
+use jsonrpc_core::{Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sr_primitives::{generic::BlockId, traits::{Block as BlockT, ProvideRuntimeApi}};
+use treasury_reward_runtime_api::InflationProjection;
+
+pub use treasury_reward_runtime_api::InflationApi as InflationRuntimeApi;
+
+/// Inflation RPC methods.
+#[rpc]
+pub trait InflationApi<BlockHash> {
+	/// Returns the staking payout per era, the treasury mint per block, the
+	/// fee split and the total issuance projected `blocks` ahead.
+	#[rpc(name = "edgeware_projectedInflation")]
+	fn projected_inflation(
+		&self,
+		blocks: BlockNumber,
+		at: Option<BlockHash>,
+	) -> Result<InflationProjection<BlockNumber, Balance>>;
+}
+
+/// An implementation of inflation specific RPC methods.
+pub struct Inflation<C> {
+	client: Arc<C>,
+}
+
+impl<C> Inflation<C> {
+	/// Create new `Inflation` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Inflation { client }
+	}
+}
+
+impl<C> InflationApi<<Block as BlockT>::Hash> for Inflation<C> where
+	C: ProvideRuntimeApi,
+	C: client::blockchain::HeaderBackend<Block>,
+	C: Send + Sync + 'static,
+	C::Api: InflationRuntimeApi<Block, BlockNumber, Balance>,
+{
+	fn projected_inflation(
+		&self,
+		blocks: BlockNumber,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<InflationProjection<BlockNumber, Balance>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.projected_inflation(&at, blocks).map_err(|e| Error {
+			code: ErrorCode::ServerError(1),
+			message: "Unable to project inflation.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
@@ -31,11 +31,13 @@
 
 use std::sync::Arc;
 
-use edgeware_primitives::{Block, AccountId, Index, Balance};
+use edgeware_primitives::{Block, BlockNumber, AccountId, Index, Balance};
 use edgeware_runtime::UncheckedExtrinsic;
 use sr_primitives::traits::ProvideRuntimeApi;
 use txpool_api::TransactionPool;
 
+pub mod inflation;
+
 /// Light client extra dependencies.
 pub struct LightDeps<F> {
 	/// Remote access to the blockchain (async).
@@ -68,6 +70,7 @@ pub fn create<C, P, M, F>(
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	C::Api: inflation::InflationRuntimeApi<Block, BlockNumber, Balance>,
 	F: client::light::fetcher::Fetcher<Block> + 'static,
 	P: TransactionPool + 'static,
 	M: jsonrpc_core::Metadata + Default,
@@ -75,6 +78,7 @@ pub fn create<C, P, M, F>(
 	use substrate_frame_rpc_system::{FullSystem, LightSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use inflation::{Inflation, InflationApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 
@@ -94,7 +98,10 @@ pub fn create<C, P, M, F>(
 			ContractsApi::to_delegate(Contracts::new(client.clone()))
 		);
 		io.extend_with(
-			TransactionPaymentApi::to_delegate(TransactionPayment::new(client))
+			TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
+		);
+		io.extend_with(
+			InflationApi::to_delegate(Inflation::new(client))
 		);
 	}
 	io
@@ -16,12 +16,13 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use edgeware_primitives::Balance;
+use edgeware_primitives::{AccountId, Balance};
+use primitives::u32_trait::{_1, _2};
 use support::weights::Weight;
 use sp_runtime::traits::{Convert, Saturating};
 use sp_runtime::{Fixed64, Perbill};
-use support::traits::{OnUnbalanced, Currency, Get};
-use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance};
+use support::traits::{OnUnbalanced, Currency, Get, EnsureOrigin};
+use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance, Origin, CouncilCollective};
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -30,6 +31,18 @@ impl OnUnbalanced<NegativeImbalance> for Author {
 	}
 }
 
+/// Origin check that passes for root, e.g. sudo or a referendum, or for a
+/// straight majority of the council.
+pub struct EnsureRootOrHalfCouncil;
+impl EnsureOrigin<Origin> for EnsureRootOrHalfCouncil {
+	type Success = ();
+	fn try_origin(o: Origin) -> Result<Self::Success, Origin> {
+		<system::EnsureRoot<AccountId> as EnsureOrigin<Origin>>::try_origin(o)
+			.or_else(|o| <collective::EnsureProportionAtLeast<_1, _2, AccountId, CouncilCollective>
+				as EnsureOrigin<Origin>>::try_origin(o))
+	}
+}
+
 /// Struct that handles the conversion of Balance -> `u64`. This is used for staking's election
 /// calculation.
 pub struct CurrencyToVoteHandler;
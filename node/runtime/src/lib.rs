@@ -24,7 +24,7 @@ use rstd::prelude::*;
 use support::{
 	construct_runtime, parameter_types, traits::{SplitTwoWays, Currency, Randomness}
 };
-use primitives::u32_trait::{_1, _2, _3, _4};
+use primitives::u32_trait::{_1, _2, _3, _4, Value as U32Value};
 use edgeware_primitives::{AccountId, AccountIndex, Balance, BlockNumber, Hash, Index, Moment, Signature};
 use sr_api::impl_runtime_apis;
 use sp_runtime::{Permill, Perbill, ApplyExtrinsicResult, impl_opaque_keys, generic, create_runtime_str};
@@ -46,6 +46,7 @@ use im_online::ed25519::{AuthorityId as ImOnlineId};
 use authority_discovery_primitives::AuthorityId as AuthorityDiscoveryId;
 use transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
 use contracts_rpc_runtime_api::ContractExecResult;
+use treasury_reward_runtime_api::{InflationProjection, TreasuryMintingInfo};
 use system::offchain::TransactionSubmitter;
 
 
@@ -56,10 +57,12 @@ pub use balances::Call as BalancesCall;
 pub use contracts::Gas;
 pub use support::StorageValue;
 pub use staking::StakerStatus;
+pub use identity::{IdentityCharset, IdentityTypeRules, default_identity_types};
+pub use treasury_reward::MintingMode;
 
 /// Implementations of some helper traits passed into runtime modules as associated types.
 pub mod impls;
-use impls::{CurrencyToVoteHandler, Author, LinearWeightToFee, TargetedFeeAdjustment};
+use impls::{CurrencyToVoteHandler, Author, LinearWeightToFee, TargetedFeeAdjustment, EnsureRootOrHalfCouncil};
 
 /// Constant values used within the runtime.
 pub mod constants;
@@ -78,8 +81,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to equal spec_version. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 27,
-	impl_version: 27,
+	spec_version: 28,
+	impl_version: 28,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -94,11 +97,16 @@ pub fn native_version() -> NativeVersion {
 
 type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
 
+/// Parts of each fee that go to the treasury.
+type FeeTreasuryParts = _4;
+/// Parts of each fee that go to the block author.
+type FeeAuthorParts = _1;
+
 pub type DealWithFees = SplitTwoWays<
 	Balance,
 	NegativeImbalance,
-	_4, Treasury,   // 4 parts (80%) goes to the treasury.
-	_1, Author,     // 1 part (20%) goes to the block author.
+	FeeTreasuryParts, Treasury,   // 4 parts (80%) goes to the treasury.
+	FeeAuthorParts, Author,       // 1 part (20%) goes to the block author.
 >;
 
 parameter_types! {
@@ -485,19 +493,36 @@ impl system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for Runtim
 	}
 }
 
+type SubmitIdentityTransaction = TransactionSubmitter<identity::crypto::Public, Runtime, UncheckedExtrinsic>;
+
 impl identity::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
+	/// Root or a straight majority of the council can manage the verifier set.
+	type VerifierOrigin = EnsureRootOrHalfCouncil;
+	type Slashed = Treasury;
+	type Call = Call;
+	type SubmitTransaction = identity::SignedSubmitter<SubmitIdentityTransaction>;
+	type StatementSignature = Signature;
+	type StatementSigner = <Signature as traits::Verify>::Signer;
 }
 
 impl signaling::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
+	type IdentityProvider = Identity;
+}
+
+parameter_types! {
+	pub const BlocksPerYear: BlockNumber = 365 * DAYS;
 }
 
 impl treasury_reward::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
+	/// Root or a straight majority of the council can change the treasury minting.
+	type MintingOrigin = EnsureRootOrHalfCouncil;
+	type BlocksPerYear = BlocksPerYear;
 }
 
 impl voting::Trait for Runtime {
@@ -712,6 +737,52 @@ impl_runtime_apis! {
 			TransactionPayment::query_info(uxt, len)
 		}
 	}
+
+	impl treasury_reward_runtime_api::TreasuryRewardApi<Block, AccountId, Balance> for Runtime {
+		fn treasury_minting() -> TreasuryMintingInfo<Balance> {
+			TreasuryMintingInfo {
+				total_minted: TreasuryReward::total_minted(),
+				treasury_minted: TreasuryReward::minted_to(Treasury::account_id()),
+				treasury_balance: TreasuryReward::pot(),
+			}
+		}
+
+		fn minted_to(who: AccountId) -> Balance {
+			TreasuryReward::minted_to(who)
+		}
+	}
+
+	impl treasury_reward_runtime_api::InflationApi<Block, BlockNumber, Balance> for Runtime {
+		fn projected_inflation(blocks: BlockNumber) -> InflationProjection<BlockNumber, Balance> {
+			let era_length = Period::get() * SessionsPerEra::get();
+			let total_issuance = Balances::total_issuance();
+			// Staking rewards the stake of its elected validators at the slot stake.
+			let staked = Staking::slot_stake()
+				.saturating_mul(Staking::current_elected().len() as Balance);
+			let (era_staking_payout, era_max_payout) = staking::inflation::compute_total_payout(
+				&REWARD_CURVE,
+				staked,
+				total_issuance,
+				(era_length as Moment).saturating_mul(MILLISECS_PER_BLOCK),
+			);
+			let eras = (blocks / era_length) as Balance;
+			let projected_issuance = total_issuance
+				.saturating_add(era_max_payout.saturating_mul(eras))
+				.saturating_add(TreasuryReward::projected_minting(blocks));
+			let fee_parts = FeeTreasuryParts::VALUE + FeeAuthorParts::VALUE;
+			InflationProjection {
+				era_length,
+				era_staking_payout,
+				era_staking_remainder: era_max_payout.saturating_sub(era_staking_payout),
+				block_treasury_mint: TreasuryReward::payout_per_block(),
+				fee_treasury_share: Perbill::from_rational_approximation(FeeTreasuryParts::VALUE, fee_parts),
+				fee_author_share: Perbill::from_rational_approximation(FeeAuthorParts::VALUE, fee_parts),
+				total_issuance,
+				blocks,
+				projected_issuance,
+			}
+		}
+	}
 }
 
 #[cfg(test)]